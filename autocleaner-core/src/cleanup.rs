@@ -0,0 +1,985 @@
+use crate::{
+    config::{DeleteMode, RuleCombination, TimestampBasis, TrackedFolder},
+    progress::Progress,
+    quarantine::{get_quarantine_dir, QuarantineBatch},
+    trash::move_to_trash,
+    walker::{walk, WalkResult},
+};
+use chrono::{DateTime, Duration, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, Metadata},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Describes which cleanup rule caused a file to be selected for removal.
+///
+/// # Variants
+///
+/// - `Age`: The file is older than the folder's `days` threshold.
+/// - `Size`: The file is larger than the folder's `max_file_size`.
+/// - `Retention`: The file is not among the newest `keep` files of its group.
+/// - `All`: Every enabled per-file rule matched, for folders combining rules with
+///   [`RuleCombination::All`]. Disabled rules are `None`.
+/// - `Quota`: The folder exceeds its `quota`, and the file is among the oldest files
+///   that have to go to bring it back under the limit.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchedRule {
+    Age {
+        days: u32,
+    },
+    Size {
+        max_bytes: u64,
+    },
+    Retention {
+        keep: usize,
+    },
+    All {
+        days: Option<u32>,
+        max_bytes: Option<u64>,
+        keep: Option<usize>,
+    },
+    Quota {
+        limit_bytes: u64,
+    },
+}
+
+impl std::fmt::Display for MatchedRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchedRule::Age { days } => write!(f, "older than {} days", days),
+            MatchedRule::Size { max_bytes } => write!(f, "larger than {} bytes", max_bytes),
+            MatchedRule::Retention { keep } => write!(f, "not among the newest {}", keep),
+            MatchedRule::All {
+                days,
+                max_bytes,
+                keep,
+            } => {
+                let parts: Vec<String> = [
+                    days.map(|days| MatchedRule::Age { days }),
+                    max_bytes.map(|max_bytes| MatchedRule::Size { max_bytes }),
+                    keep.map(|keep| MatchedRule::Retention { keep }),
+                ]
+                .iter()
+                .flatten()
+                .map(ToString::to_string)
+                .collect();
+                write!(f, "{}", parts.join(" and "))
+            }
+            MatchedRule::Quota { limit_bytes } => {
+                write!(f, "over folder quota of {} bytes", limit_bytes)
+            }
+        }
+    }
+}
+
+/// Classifies why a file could not be scanned or removed.
+///
+/// # Variants
+///
+/// - `PermissionDenied`: The current user lacks the rights to access or remove the file.
+/// - `InUse`: The file is locked or busy, for example opened by another process on Windows.
+/// - `NotFound`: The file disappeared before it could be accessed or removed.
+/// - `InvalidPattern`: The folder's include, exclude or retention patterns failed to
+///   compile.
+/// - `Io`: Any other I/O error.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CleanupErrorKind {
+    PermissionDenied,
+    InUse,
+    NotFound,
+    InvalidPattern,
+    Io,
+}
+
+impl CleanupErrorKind {
+    /// Classifies an I/O error.
+    pub fn from_io(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => CleanupErrorKind::PermissionDenied,
+            io::ErrorKind::NotFound => CleanupErrorKind::NotFound,
+            io::ErrorKind::ResourceBusy | io::ErrorKind::ExecutableFileBusy => {
+                CleanupErrorKind::InUse
+            }
+            _ if is_in_use_error(error) => CleanupErrorKind::InUse,
+            _ => CleanupErrorKind::Io,
+        }
+    }
+}
+
+impl std::fmt::Display for CleanupErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CleanupErrorKind::PermissionDenied => write!(f, "permission denied"),
+            CleanupErrorKind::InUse => write!(f, "in use"),
+            CleanupErrorKind::NotFound => write!(f, "not found"),
+            CleanupErrorKind::InvalidPattern => write!(f, "invalid pattern"),
+            CleanupErrorKind::Io => write!(f, "I/O error"),
+        }
+    }
+}
+
+/// Returns `true` if `error` is a Windows sharing or lock violation.
+#[cfg(windows)]
+fn is_in_use_error(error: &io::Error) -> bool {
+    /// `ERROR_SHARING_VIOLATION`: the file is opened by another process.
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    /// `ERROR_LOCK_VIOLATION`: part of the file is locked by another process.
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    matches!(
+        error.raw_os_error(),
+        Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+    )
+}
+
+/// Returns `true` if `error` reports a busy file; `std` already maps these on Unix.
+#[cfg(not(windows))]
+fn is_in_use_error(_error: &io::Error) -> bool {
+    false
+}
+
+/// A file or directory that could not be scanned or removed.
+///
+/// # Fields
+///
+/// - `path`: The affected path. For invalid patterns, this is the tracked folder.
+/// - `kind`: The [`CleanupErrorKind`] classifying the failure.
+/// - `message`: The underlying error message.
+///
+#[derive(Clone, Debug)]
+pub struct CleanupError {
+    pub path: PathBuf,
+    pub kind: CleanupErrorKind,
+    pub message: String,
+}
+
+impl CleanupError {
+    /// Creates an error for `path` from an I/O error.
+    pub fn from_io(path: &Path, error: &io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            kind: CleanupErrorKind::from_io(error),
+            message: error.to_string(),
+        }
+    }
+
+    /// Creates an error from a failed directory walk step below `root`.
+    pub fn from_walk(root: &Path, error: &walkdir::Error) -> Self {
+        let path = error.path().unwrap_or(root);
+        match error.io_error() {
+            Some(io_error) => Self::from_io(path, io_error),
+            None => Self {
+                path: path.to_path_buf(),
+                kind: CleanupErrorKind::Io,
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for CleanupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {}",
+            self.path.display(),
+            self.kind,
+            self.message
+        )
+    }
+}
+
+/// A single file that a [`CleanupPlan`] intends to remove.
+///
+/// # Fields
+///
+/// - `path`: The full path to the file.
+/// - `size`: The size of the file in bytes at scan time.
+/// - `modified`: The last modified timestamp of the file at scan time.
+/// - `timestamp`: The timestamp the age-based rules used for this file.
+/// - `basis`: Which timestamp `timestamp` is. This differs from the folder's requested
+///   basis when the file did not provide it and fell back to the modified time.
+/// - `rule`: The [`MatchedRule`] that selected this file.
+///
+#[derive(Clone, Debug)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+    pub timestamp: DateTime<Utc>,
+    pub basis: TimestampBasis,
+    pub rule: MatchedRule,
+}
+
+/// A dry-run listing of every file that a cleanup of a tracked folder would remove.
+///
+/// A plan is produced by [`scan_folder`] and executed by [`remove_old_files`], so the
+/// files that get deleted are always exactly the files that were previewed.
+///
+/// # Fields
+///
+/// - `root`: The tracked folder the plan was built for.
+/// - `delete_mode`: How the files are disposed of when the plan is executed.
+/// - `timestamp_basis`: The timestamp the folder asked the age-based rules to use.
+/// - `timestamp_fallbacks`: How many scanned files lacked that timestamp and were judged
+///   by their modified time instead.
+/// - `prune`: If set, how directories left empty by the cleanup are pruned afterwards.
+/// - `cancelled`: Whether the scan was cancelled. A cancelled plan contains no files.
+/// - `errors`: Paths that could not be scanned, and invalid patterns.
+/// - `files`: The candidate files, in path order.
+///
+#[derive(Clone, Debug, Default)]
+pub struct CleanupPlan {
+    pub root: String,
+    pub delete_mode: DeleteMode,
+    pub timestamp_basis: TimestampBasis,
+    pub timestamp_fallbacks: usize,
+    pub prune: Option<DirPruning>,
+    pub cancelled: bool,
+    pub errors: Vec<CleanupError>,
+    pub files: Vec<PlannedFile>,
+}
+
+impl CleanupPlan {
+    /// Returns the number of files in the plan.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if the plan contains no files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the combined size in bytes of all files in the plan.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+}
+
+/// Settings for removing directories that a cleanup leaves empty.
+///
+/// # Fields
+///
+/// - `min_age_days`: How many days a directory must have gone unmodified before the
+///   cleanup for it to be removed.
+/// - `exclude`: The folder's exclude patterns; matching directories are kept.
+///
+#[derive(Clone, Debug, Default)]
+pub struct DirPruning {
+    pub min_age_days: u32,
+    pub exclude: Vec<String>,
+}
+
+/// A planned file that was removed.
+///
+/// # Fields
+///
+/// - `file`: The [`PlannedFile`] as it was planned.
+/// - `location`: Where the file was moved to in the trash or quarantine, if known.
+///
+#[derive(Clone, Debug)]
+pub struct RemovedFile {
+    pub file: PlannedFile,
+    pub location: Option<PathBuf>,
+}
+
+/// A planned file that was left in place because it no longer matches the plan.
+///
+/// # Fields
+///
+/// - `file`: The [`PlannedFile`] as it was planned.
+/// - `reason`: Why the file was skipped.
+///
+#[derive(Clone, Debug)]
+pub struct SkippedFile {
+    pub file: PlannedFile,
+    pub reason: String,
+}
+
+/// The outcome of executing a [`CleanupPlan`].
+///
+/// # Fields
+///
+/// - `deleted`: Files that were removed.
+/// - `skipped`: Files that changed since the scan and were left in place.
+/// - `failed`: Files that could not be removed, and directories that could not be pruned,
+///   with the reason.
+/// - `pruned_dirs`: The number of empty directories removed afterwards.
+/// - `cancelled`: Whether the cleanup was cancelled before every file was processed.
+///
+#[derive(Clone, Debug, Default)]
+pub struct CleanupReport {
+    pub deleted: Vec<RemovedFile>,
+    pub skipped: Vec<SkippedFile>,
+    pub failed: Vec<CleanupError>,
+    pub pruned_dirs: usize,
+    pub cancelled: bool,
+}
+
+impl CleanupReport {
+    /// Returns the combined size in bytes of all removed files.
+    pub fn deleted_size(&self) -> u64 {
+        self.deleted.iter().map(|r| r.file.size).sum()
+    }
+
+    /// Returns a one-line summary of the report, e.g. `3 deleted, 1 skipped, 0 failed`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} deleted, {} skipped, {} failed{}",
+            self.deleted.len(),
+            self.skipped.len(),
+            self.failed.len(),
+            if self.cancelled { ", cancelled" } else { "" }
+        )
+    }
+}
+
+/// The compiled include and exclude patterns of a [`TrackedFolder`].
+///
+/// Patterns are matched against paths relative to the tracked folder. A leading `!` on
+/// an exclude pattern is accepted and ignored, so `!**/.git/**` and `**/.git/**` are
+/// equivalent.
+///
+/// # Fields
+///
+/// - `include`: Files must match one of these patterns, or `None` to include every file.
+/// - `exclude`: Files matching one of these patterns are skipped.
+/// - `exclude_dirs`: Directories matching one of these patterns are not descended into.
+///   Besides the exclude patterns themselves, it holds `dir` for every `dir/**` pattern.
+///
+pub struct FileFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    exclude_dirs: GlobSet,
+}
+
+impl FileFilter {
+    /// Compiles include and exclude patterns.
+    ///
+    /// # Returns
+    ///
+    /// - The compiled filter, or the first pattern error encountered.
+    ///
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, globset::Error> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in include {
+                builder.add(Glob::new(pattern)?);
+            }
+            Some(builder.build()?)
+        };
+
+        let mut files = GlobSetBuilder::new();
+        let mut dirs = GlobSetBuilder::new();
+        for pattern in exclude {
+            let pattern = pattern.strip_prefix('!').unwrap_or(pattern);
+            files.add(Glob::new(pattern)?);
+            dirs.add(Glob::new(pattern)?);
+            if let Some(dir) = pattern.strip_suffix("/**") {
+                dirs.add(Glob::new(dir)?);
+            }
+        }
+
+        Ok(Self {
+            include,
+            exclude: files.build()?,
+            exclude_dirs: dirs.build()?,
+        })
+    }
+
+    /// Compiles the patterns of a tracked folder.
+    pub fn for_folder(folder: &TrackedFolder) -> Result<Self, globset::Error> {
+        Self::new(&folder.include, &folder.exclude)
+    }
+
+    /// Returns `true` if the directory at `relative` should not be descended into.
+    pub fn is_excluded_dir(&self, relative: &Path) -> bool {
+        self.exclude_dirs.is_match(relative)
+    }
+
+    /// Returns `true` if the file at `relative` may be selected for cleanup.
+    pub fn is_included_file(&self, relative: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|set| set.is_match(relative))
+            && !self.exclude.is_match(relative)
+    }
+}
+
+/// A file found while walking a tracked folder, before any rule is applied.
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    modified: DateTime<Utc>,
+    timestamp: DateTime<Utc>,
+    basis: TimestampBasis,
+}
+
+impl Candidate {
+    /// Reads a candidate's size and timestamps from its metadata.
+    ///
+    /// If the file does not provide the `requested` timestamp, its modified time is used
+    /// and `basis` records the fallback.
+    ///
+    /// # Returns
+    ///
+    /// - `None` if the file does not even provide a modified time.
+    ///
+    fn new(path: PathBuf, metadata: &Metadata, requested: TimestampBasis) -> Option<Self> {
+        let modified = DateTime::<Utc>::from(metadata.modified().ok()?);
+        let (timestamp, basis) = match read_timestamp(metadata, requested) {
+            Some(timestamp) => (timestamp, requested),
+            None => (modified, TimestampBasis::Modified),
+        };
+
+        Some(Self {
+            path,
+            size: metadata.len(),
+            modified,
+            timestamp,
+            basis,
+        })
+    }
+
+    /// Turns the candidate into a planned file selected by `rule`.
+    fn into_planned(self, rule: MatchedRule) -> PlannedFile {
+        PlannedFile {
+            path: self.path,
+            size: self.size,
+            modified: self.modified,
+            timestamp: self.timestamp,
+            basis: self.basis,
+            rule,
+        }
+    }
+}
+
+/// Reads the timestamp selected by `basis` from a file's metadata.
+///
+/// # Returns
+///
+/// - `None` if the platform or filesystem does not provide that timestamp.
+///
+fn read_timestamp(metadata: &Metadata, basis: TimestampBasis) -> Option<DateTime<Utc>> {
+    match basis {
+        TimestampBasis::Modified => metadata.modified().ok().map(DateTime::from),
+        TimestampBasis::Accessed => metadata.accessed().ok().map(DateTime::from),
+        TimestampBasis::Created => metadata.created().ok().map(DateTime::from),
+        TimestampBasis::Changed => changed_time(metadata),
+    }
+}
+
+/// Returns the status change time (ctime) of a file.
+#[cfg(unix)]
+fn changed_time(metadata: &Metadata) -> Option<DateTime<Utc>> {
+    use std::os::unix::fs::MetadataExt;
+
+    DateTime::from_timestamp(metadata.ctime(), metadata.ctime_nsec().try_into().ok()?)
+}
+
+/// Returns `None`, as the status change time is not available through `std` on this
+/// platform.
+#[cfg(not(unix))]
+fn changed_time(_metadata: &Metadata) -> Option<DateTime<Utc>> {
+    None
+}
+
+/// Scans a tracked folder and builds a [`CleanupPlan`] of files selected by its rules.
+///
+/// This function walks recursively through the folder with [`walk`] and collects every
+/// regular file that passes the folder's include and exclude patterns, in path order.
+/// Excluded directories are pruned from the walk. The folder's rules are then applied to
+/// the collected files:
+///
+/// - Files older than `folder.days` are selected, if the age rule is enabled. Age is
+///   measured with the timestamp chosen by `folder.timestamp`.
+/// - Files larger than `folder.max_file_size` are selected.
+/// - If the remaining files still exceed `folder.quota`, the oldest of them are selected
+///   until the folder is back under the limit.
+///
+/// Nothing is deleted. Paths that cannot be read are recorded in the plan's `errors`.
+/// If `progress` is cancelled during the walk, the plan is marked as cancelled and no
+/// file is selected.
+/// If the folder's include, exclude or retention patterns fail to compile, no file is
+/// selected and the pattern error is recorded instead.
+///
+/// # Parameters
+///
+/// - `folder`: The [`TrackedFolder`] that should be scanned.
+/// - `workers`: The number of threads walking the folder, where `0` means one per CPU core.
+/// - `progress`: The [`Progress`] receiving visited files and matches as they are found.
+///
+/// # Returns
+///
+/// A [`CleanupPlan`] listing every file that a cleanup would remove.
+///
+pub fn scan_folder(folder: &TrackedFolder, workers: usize, progress: &Progress) -> CleanupPlan {
+    let root = Path::new(&folder.path);
+    let threshold = Utc::now() - Duration::days(folder.days.into());
+    let mut candidates = Vec::new();
+    let mut cancelled = false;
+
+    let patterns =
+        FileFilter::for_folder(folder).and_then(|filter| Ok((filter, retention_groups(folder)?)));
+    let mut groups = None;
+
+    let errors = match patterns {
+        Ok((filter, retention_groups)) => {
+            groups = retention_groups;
+            let visitor = |path: &Path, metadata: &Metadata| {
+                if let Some(candidate) =
+                    Candidate::new(path.to_path_buf(), metadata, folder.timestamp)
+                {
+                    if early_match(folder, threshold, &candidate) == Some(true) {
+                        progress.add_match(candidate.size);
+                    }
+                }
+            };
+            let WalkResult {
+                files,
+                mut errors,
+                cancelled: walk_cancelled,
+            } = walk(root, &filter, workers, progress, &visitor);
+            cancelled = walk_cancelled;
+            for (path, metadata) in files {
+                match Candidate::new(path.clone(), &metadata, folder.timestamp) {
+                    Some(candidate) => candidates.push(candidate),
+                    None => errors.push(CleanupError {
+                        path,
+                        kind: CleanupErrorKind::Io,
+                        message: "modified time is unavailable".to_string(),
+                    }),
+                }
+            }
+            errors
+        }
+        Err(e) => vec![CleanupError {
+            path: root.to_path_buf(),
+            kind: CleanupErrorKind::InvalidPattern,
+            message: e.to_string(),
+        }],
+    };
+
+    let timestamp_fallbacks = candidates
+        .iter()
+        .filter(|c| c.basis != folder.timestamp)
+        .count();
+
+    // A partial walk must not be cleaned up, as the retention and quota rules would
+    // judge the files against an incomplete folder.
+    let files = if cancelled {
+        Vec::new()
+    } else {
+        select_files(folder, threshold, groups.as_ref(), candidates)
+    };
+    progress.set_matches(files.len(), files.iter().map(|f| f.size).sum());
+
+    CleanupPlan {
+        root: folder.path.clone(),
+        delete_mode: folder.delete_mode,
+        timestamp_basis: folder.timestamp,
+        timestamp_fallbacks,
+        prune: folder.prune_empty_dirs.then(|| DirPruning {
+            min_age_days: folder.prune_min_age_days,
+            exclude: folder.exclude.clone(),
+        }),
+        cancelled,
+        errors,
+        files,
+    }
+}
+
+/// Evaluates the age and size rules for a file while the walk is still running.
+///
+/// # Returns
+///
+/// - `Some(true)` or `Some(false)` if the file is known to be selected or not.
+/// - `None` if the outcome depends on the retention rule, which needs every file in the
+///   folder. The quota rule is not considered.
+///
+fn early_match(
+    folder: &TrackedFolder,
+    threshold: DateTime<Utc>,
+    candidate: &Candidate,
+) -> Option<bool> {
+    let rules = [
+        folder.age_enabled.then(|| candidate.timestamp < threshold),
+        folder
+            .max_file_size
+            .map(|max_bytes| candidate.size > max_bytes),
+    ];
+    let retention = folder.keep_newest.is_some();
+
+    match folder.combine {
+        RuleCombination::Any if rules.contains(&Some(true)) => Some(true),
+        RuleCombination::All if rules.contains(&Some(false)) => Some(false),
+        _ if retention => None,
+        RuleCombination::Any => Some(false),
+        RuleCombination::All => Some(rules.iter().any(Option::is_some)),
+    }
+}
+
+/// Applies a folder's age, size, retention and quota rules to the files found in it.
+///
+/// The age, size and retention rules are evaluated per file and combined according to
+/// `folder.combine`. The quota rule then selects the oldest of the remaining files.
+///
+/// # Parameters
+///
+/// - `folder`: The [`TrackedFolder`] whose rules are applied.
+/// - `threshold`: Files with a timestamp before this are matched by the age rule.
+/// - `groups`: The compiled `group_by` patterns of the folder's retention rule, if any.
+/// - `candidates`: The files found in the folder, in path order.
+///
+/// # Returns
+///
+/// The selected files: those matched by the per-file rules in path order,
+/// followed by the files selected by the quota rule, oldest first.
+///
+fn select_files(
+    folder: &TrackedFolder,
+    threshold: DateTime<Utc>,
+    groups: Option<&GlobSet>,
+    candidates: Vec<Candidate>,
+) -> Vec<PlannedFile> {
+    let excess = folder
+        .keep_newest
+        .as_ref()
+        .map(|retention| retention_excess(retention.keep, groups, &candidates));
+    let mut selected = Vec::new();
+    let mut kept = Vec::new();
+
+    for (index, candidate) in candidates.into_iter().enumerate() {
+        // Each enabled rule yields whether it matches, paired with the rule itself.
+        let rules = [
+            folder.age_enabled.then(|| {
+                (
+                    candidate.timestamp < threshold,
+                    MatchedRule::Age { days: folder.days },
+                )
+            }),
+            folder
+                .max_file_size
+                .map(|max_bytes| (candidate.size > max_bytes, MatchedRule::Size { max_bytes })),
+            folder
+                .keep_newest
+                .as_ref()
+                .zip(excess.as_ref())
+                .map(|(r, excess)| (excess[index], MatchedRule::Retention { keep: r.keep })),
+        ];
+
+        let rule = match folder.combine {
+            RuleCombination::Any => rules
+                .iter()
+                .flatten()
+                .find(|(matched, _)| *matched)
+                .map(|&(_, rule)| rule),
+            RuleCombination::All => {
+                let enabled: Vec<_> = rules.iter().flatten().collect();
+                match enabled.as_slice() {
+                    [] => None,
+                    _ if enabled.iter().any(|(matched, _)| !matched) => None,
+                    [(_, rule)] => Some(*rule),
+                    _ => Some(MatchedRule::All {
+                        days: rules[0].map(|_| folder.days),
+                        max_bytes: folder.max_file_size,
+                        keep: folder.keep_newest.as_ref().map(|r| r.keep),
+                    }),
+                }
+            }
+        };
+
+        match rule {
+            Some(rule) => selected.push(candidate.into_planned(rule)),
+            None => kept.push(candidate),
+        }
+    }
+
+    if let Some(limit_bytes) = folder.quota {
+        let mut total: u64 = kept.iter().map(|c| c.size).sum();
+        kept.sort_by_key(|c| c.timestamp);
+        for candidate in kept {
+            if total <= limit_bytes {
+                break;
+            }
+            total -= candidate.size;
+            selected.push(candidate.into_planned(MatchedRule::Quota { limit_bytes }));
+        }
+    }
+
+    selected
+}
+
+/// Compiles the `group_by` patterns of a folder's
+/// [`Retention`](crate::config::Retention) rule.
+///
+/// # Returns
+///
+/// - The compiled patterns, `None` if the folder has no retention rule or it groups
+///   nothing, or the first pattern error encountered.
+///
+fn retention_groups(folder: &TrackedFolder) -> Result<Option<GlobSet>, globset::Error> {
+    let Some(retention) = folder.keep_newest.as_ref() else {
+        return Ok(None);
+    };
+    if retention.group_by.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in &retention.group_by {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build().map(Some)
+}
+
+/// Determines which files fall outside a [`Retention`](crate::config::Retention) rule.
+///
+/// Files are grouped by the first `group_by` pattern their file name matches, or put into
+/// a single group if there are no patterns. Within each group, files other than the
+/// `keep` newest ones by the folder's timestamp basis are in excess. Files outside every
+/// group are never in excess.
+///
+/// # Parameters
+///
+/// - `keep`: How many of the newest files to keep in each group.
+/// - `groups`: The compiled `group_by` patterns, or `None` to put every file in one group.
+/// - `candidates`: The files found in the folder.
+///
+/// # Returns
+///
+/// One flag per candidate, `true` if the candidate is in excess.
+///
+fn retention_excess(keep: usize, groups: Option<&GlobSet>, candidates: &[Candidate]) -> Vec<bool> {
+    let mut excess = vec![false; candidates.len()];
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, candidate) in candidates.iter().enumerate() {
+        let group = match groups {
+            None => Some(0),
+            Some(set) => candidate
+                .path
+                .file_name()
+                .and_then(|name| set.matches(name).first().copied()),
+        };
+        if let Some(group) = group {
+            members.entry(group).or_default().push(index);
+        }
+    }
+
+    for mut indices in members.into_values() {
+        indices.sort_by_key(|&i| std::cmp::Reverse(candidates[i].timestamp));
+        for &i in indices.iter().skip(keep) {
+            excess[i] = true;
+        }
+    }
+
+    excess
+}
+
+/// Removes every file listed in the given [`CleanupPlan`].
+///
+/// Only the files recorded in the plan are touched; the folder is not walked again.
+/// Files whose size or modified time changed since the scan are skipped, so only the
+/// previewed files are removed. The others are moved to the trash, quarantined, or
+/// deleted permanently according to `plan.delete_mode`. If `plan.prune` is set,
+/// directories left empty afterwards are removed as described in [`prune_empty_dirs`].
+///
+/// Once `progress` is cancelled, the remaining files are left in place. Files already
+/// removed stay removed, and empty directories are still pruned.
+///
+/// # Parameters
+///
+/// - `plan`: The plan produced by [`scan_folder`].
+/// - `progress`: The [`Progress`] counting processed and removed files.
+///
+/// # Returns
+///
+/// A [`CleanupReport`] separating removed, skipped and failed files.
+///
+pub fn remove_old_files(plan: &CleanupPlan, progress: &Progress) -> CleanupReport {
+    // Directory ages must be read before removing files bumps their modified times.
+    let dirs = plan
+        .prune
+        .as_ref()
+        .map(|_| parent_dirs(plan))
+        .unwrap_or_default();
+
+    let mut report = CleanupReport::default();
+    let mut disposer = Disposer::new(plan);
+
+    for file in &plan.files {
+        if progress.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        progress.add_visited();
+
+        let metadata = match fs::metadata(&file.path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                report.failed.push(CleanupError::from_io(&file.path, &e));
+                continue;
+            }
+        };
+
+        let modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+        if metadata.len() != file.size || modified != Some(file.modified) {
+            report.skipped.push(SkippedFile {
+                file: file.clone(),
+                reason: "changed since the scan".to_string(),
+            });
+            continue;
+        }
+
+        match disposer.dispose(&file.path) {
+            Ok(location) => {
+                progress.add_match(file.size);
+                report.deleted.push(RemovedFile {
+                    file: file.clone(),
+                    location,
+                });
+            }
+            Err(e) => report.failed.push(CleanupError::from_io(&file.path, &e)),
+        }
+    }
+
+    if let Err(e) = disposer.finish() {
+        report
+            .failed
+            .push(CleanupError::from_io(&get_quarantine_dir(), &e));
+    }
+
+    if let Some(pruning) = &plan.prune {
+        let root = Path::new(&plan.root);
+        if let Err(e) = prune_empty_dirs(root, pruning, dirs, &mut report) {
+            report.failed.push(CleanupError {
+                path: root.to_path_buf(),
+                kind: CleanupErrorKind::InvalidPattern,
+                message: e.to_string(),
+            });
+        }
+    }
+
+    report
+}
+
+/// Collects every directory between the planned files and the tracked folder.
+///
+/// # Returns
+///
+/// Each directory below `plan.root` that contains a planned file, directly or
+/// indirectly, mapped to its modified time if available.
+///
+fn parent_dirs(plan: &CleanupPlan) -> BTreeMap<PathBuf, Option<DateTime<Utc>>> {
+    let root = Path::new(&plan.root);
+    let mut dirs = BTreeMap::new();
+
+    for file in &plan.files {
+        for dir in file.path.ancestors().skip(1) {
+            if !dir.starts_with(root) || dir == root || dirs.contains_key(dir) {
+                break;
+            }
+            let modified = fs::metadata(dir)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Utc>::from);
+            dirs.insert(dir.to_path_buf(), modified);
+        }
+    }
+
+    dirs
+}
+
+/// Removes directories that a cleanup left empty, deepest first.
+///
+/// Only the directories collected by [`parent_dirs`] are considered, so the tracked
+/// folder itself and directories that were already empty are never removed. A directory
+/// is kept if it matches an exclude pattern, if it was modified more recently than
+/// `pruning.min_age_days` before the cleanup, or if it still contains anything.
+///
+/// Removed directories are counted in `report.pruned_dirs`, and directories that could
+/// not be removed for another reason are added to `report.failed`.
+///
+/// # Returns
+///
+/// - An `Err` if the exclude patterns fail to compile. No directory is removed then.
+///
+fn prune_empty_dirs(
+    root: &Path,
+    pruning: &DirPruning,
+    dirs: BTreeMap<PathBuf, Option<DateTime<Utc>>>,
+    report: &mut CleanupReport,
+) -> Result<(), globset::Error> {
+    let filter = FileFilter::new(&[], &pruning.exclude)?;
+    let threshold = Utc::now() - Duration::days(pruning.min_age_days.into());
+
+    let mut dirs: Vec<_> = dirs.into_iter().collect();
+    dirs.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+
+    let candidates = dirs
+        .into_iter()
+        .filter(|(dir, _)| !filter.is_excluded_dir(dir.strip_prefix(root).unwrap_or(dir)))
+        .filter(|(_, modified)| modified.is_some_and(|m| m <= threshold));
+    for (dir, _) in candidates {
+        match fs::remove_dir(&dir) {
+            Ok(()) => report.pruned_dirs += 1,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::DirectoryNotEmpty | io::ErrorKind::NotFound
+                ) => {}
+            Err(e) => report.failed.push(CleanupError::from_io(&dir, &e)),
+        }
+    }
+    Ok(())
+}
+
+/// Disposes of files according to a plan's [`DeleteMode`].
+enum Disposer {
+    Trash,
+    Permanent,
+    Quarantine(QuarantineBatch),
+}
+
+impl Disposer {
+    /// Creates the disposer for the delete mode of `plan`.
+    fn new(plan: &CleanupPlan) -> Self {
+        match plan.delete_mode {
+            DeleteMode::Trash => Disposer::Trash,
+            DeleteMode::Permanent => Disposer::Permanent,
+            DeleteMode::Quarantine => Disposer::Quarantine(QuarantineBatch::new(&plan.root)),
+        }
+    }
+
+    /// Disposes of a single file.
+    ///
+    /// # Returns
+    ///
+    /// - The file's new location in the trash or quarantine, if it was moved and the
+    ///   location is known.
+    ///
+    fn dispose(&mut self, path: &Path) -> io::Result<Option<PathBuf>> {
+        match self {
+            Disposer::Trash => move_to_trash(path),
+            Disposer::Permanent => fs::remove_file(path).map(|_| None),
+            Disposer::Quarantine(batch) => batch.add(path).map(Some),
+        }
+    }
+
+    /// Completes the run, recording quarantined files in the manifest.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Disposer::Quarantine(batch) => batch.commit(),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,765 @@
+use crate::schedule::Schedule;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The default number of days a quarantined file is kept before it is purged.
+const DEFAULT_QUARANTINE_DAYS: u32 = 30;
+
+/// The file name used to store the tracked folders configuration.
+///
+/// This constant defines the name of the JSON file where the app saves and loads
+/// folder tracking data
+///
+const CONFIG_FILE_NAME: &str = "tracked_folders.json";
+
+/// The number of previous config files [`save_config`] keeps as rolling backups.
+pub const CONFIG_BACKUP_COUNT: usize = 5;
+
+/// The shortest time between two rotations of the rolling backups by this process, so a
+/// burst of saves while editing does not push every useful backup out.
+const CONFIG_BACKUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// When this process last rotated the rolling backups, if it has.
+static LAST_BACKUP: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Distinguishes the temporary files of concurrent [`write_atomic`] calls in this process.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The version of the config file format written by this build.
+///
+/// Files without a `version` field were written before the format was versioned and are
+/// version `0`. Whenever the format changes incompatibly, this is incremented and a step
+/// is appended to [`MIGRATIONS`].
+///
+pub const CONFIG_VERSION: u32 = 1;
+
+/// A step of [`MIGRATIONS`], upgrading the JSON of a config file by one version.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Upgrades a config file one version at a time, where `MIGRATIONS[n]` turns the JSON of
+/// a version `n` file into version `n + 1`. The `version` field itself is set by
+/// [`migrate`].
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Determines what happens to a file when a cleanup removes it.
+///
+/// # Variants
+///
+/// - `Trash`: The file is moved to the user's trash (or the Recycle Bin on Windows),
+///   so it can be recovered. This is the default.
+/// - `Permanent`: The file is deleted permanently with `fs::remove_file`.
+/// - `Quarantine`: The file is moved into the application's quarantine directory and
+///   permanently deleted once [`FolderConfig::quarantine_days`] have passed.
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    #[default]
+    Trash,
+    Permanent,
+    Quarantine,
+}
+
+impl std::fmt::Display for DeleteMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeleteMode::Trash => write!(f, "Move to trash"),
+            DeleteMode::Permanent => write!(f, "Delete permanently"),
+            DeleteMode::Quarantine => write!(f, "Quarantine"),
+        }
+    }
+}
+
+/// What the daemon does when a folder's [`Schedule`] fires.
+///
+/// # Variants
+///
+/// - `Notify`: The folder is scanned and a notification reports the files found. This is
+///   the default.
+/// - `Clean`: The folder is scanned and the files found are removed right away.
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledAction {
+    #[default]
+    Notify,
+    Clean,
+}
+
+impl std::fmt::Display for ScheduledAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduledAction::Notify => write!(f, "Notify only"),
+            ScheduledAction::Clean => write!(f, "Clean automatically"),
+        }
+    }
+}
+
+/// Keeps the newest files of a tracked folder and selects the rest for removal.
+///
+/// # Fields
+///
+/// - `keep`: How many of the newest files, by the folder's [`TimestampBasis`], are kept per group.
+/// - `group_by`: Glob patterns matched against file names, e.g. `backup-*.tar.gz`. Files
+///   matching the same pattern form a group and only files in a group are affected.
+///   An empty list puts every file into a single group.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Retention {
+    pub keep: usize,
+    #[serde(default)]
+    pub group_by: Vec<String>,
+}
+
+/// Determines how the per-file rules of a [`TrackedFolder`] are combined.
+///
+/// The per-file rules are the age threshold, the file size limit and the
+/// keep-the-newest-N retention. The folder quota is applied afterwards in both modes.
+///
+/// # Variants
+///
+/// - `Any`: A file is selected if any enabled rule matches it. This is the default.
+/// - `All`: A file is selected only if every enabled rule matches it, e.g. "older than
+///   30 days AND not among the newest 5".
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleCombination {
+    #[default]
+    Any,
+    All,
+}
+
+impl std::fmt::Display for RuleCombination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleCombination::Any => write!(f, "Match any rule"),
+            RuleCombination::All => write!(f, "Match all rules"),
+        }
+    }
+}
+
+/// Selects which file timestamp the age-based rules compare against.
+///
+/// When a file does not provide the requested timestamp, for example the creation time on
+/// filesystems without birth time support or the change time on Windows, its modified
+/// time is used instead and the fallback is counted in the scan result.
+///
+/// # Variants
+///
+/// - `Modified`: The last modification time (mtime). This is the default.
+/// - `Accessed`: The last access time (atime).
+/// - `Created`: The creation time (btime), where the filesystem supports it.
+/// - `Changed`: The last status change time (ctime), on Unix platforms.
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampBasis {
+    #[default]
+    Modified,
+    Accessed,
+    Created,
+    Changed,
+}
+
+impl std::fmt::Display for TimestampBasis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampBasis::Modified => write!(f, "Modified"),
+            TimestampBasis::Accessed => write!(f, "Accessed"),
+            TimestampBasis::Created => write!(f, "Created"),
+            TimestampBasis::Changed => write!(f, "Changed"),
+        }
+    }
+}
+
+/// Represents a folder that is being tracked by the application.
+///
+/// A `TrackedFolder` contains the path to a folder and a threshold in days,
+/// indicating how old a file must be to be considered for scanning or deletion.
+///
+/// # Fields
+///
+/// - `path`: The full filesystem path to the folder.
+/// - `days`: The number of days to use as the threshold for old files.
+/// - `age_enabled`: Whether files older than `days` are selected. Defaults to `true`.
+/// - `timestamp`: Which file timestamp determines a file's age. Defaults to
+///   [`TimestampBasis::Modified`].
+/// - `max_file_size`: If set, files larger than this many bytes are selected regardless of age.
+/// - `quota`: If set, the oldest remaining files are selected until the folder's total size
+///   is at most this many bytes.
+/// - `keep_newest`: If set, a [`Retention`] rule that selects every file except the newest ones.
+/// - `combine`: How the age, size and retention rules are combined. Defaults to
+///   [`RuleCombination::Any`].
+/// - `delete_mode`: How removed files are disposed of. Defaults to [`DeleteMode::Trash`].
+/// - `include`: Glob patterns, relative to `path`, that a file must match to be considered.
+///   An empty list includes every file.
+/// - `exclude`: Glob patterns, relative to `path`, for files and directories that are never
+///   considered, e.g. `**/.git/**`. Excluded directories are not descended into.
+/// - `prune_empty_dirs`: Whether directories left empty by a cleanup are removed afterwards.
+///   The tracked folder itself and excluded directories are never removed.
+/// - `prune_min_age_days`: How many days a directory must have gone unmodified before the
+///   cleanup for it to be pruned.
+/// - `schedule`: If set, when the daemon evaluates the folder. Unscheduled folders are only
+///   scanned on demand.
+/// - `scheduled_action`: What the daemon does when `schedule` fires. Defaults to
+///   [`ScheduledAction::Notify`].
+///
+/// # See Also
+///
+/// - [`FolderConfig`] — holds a collection of `TrackedFolder` items.
+///
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrackedFolder {
+    pub path: String,
+    pub days: u32,
+    #[serde(default = "default_true")]
+    pub age_enabled: bool,
+    #[serde(default)]
+    pub timestamp: TimestampBasis,
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    #[serde(default)]
+    pub quota: Option<u64>,
+    #[serde(default)]
+    pub keep_newest: Option<Retention>,
+    #[serde(default)]
+    pub combine: RuleCombination,
+    #[serde(default)]
+    pub delete_mode: DeleteMode,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub prune_empty_dirs: bool,
+    #[serde(default)]
+    pub prune_min_age_days: u32,
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    #[serde(default)]
+    pub scheduled_action: ScheduledAction,
+}
+
+impl TrackedFolder {
+    /// Creates a tracked folder with the given age threshold and default settings.
+    ///
+    /// # Parameters
+    ///
+    /// - `path`: The full filesystem path to the folder.
+    /// - `days`: The number of days to use as the threshold for old files.
+    ///
+    pub fn new(path: String, days: u32) -> Self {
+        Self {
+            path,
+            days,
+            age_enabled: true,
+            timestamp: TimestampBasis::default(),
+            max_file_size: None,
+            quota: None,
+            keep_newest: None,
+            combine: RuleCombination::default(),
+            delete_mode: DeleteMode::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            prune_empty_dirs: false,
+            prune_min_age_days: 0,
+            schedule: None,
+            scheduled_action: ScheduledAction::default(),
+        }
+    }
+}
+
+/// Holds the configuration for all tracked folders in the application.
+///
+/// `FolderConfig` wraps a collection of [`TrackedFolder`] items, representing
+/// all directories that the app is currently monitoring for cleanup purposes.
+///
+/// # Fields
+///
+/// - `version`: The format version of the file, see [`CONFIG_VERSION`].
+/// - `folders`: A list of folders to track, each with its own path and age threshold.
+/// - `quarantine_days`: The grace period in days before quarantined files are purged.
+/// - `scan_workers`: The number of threads used to walk a folder, where `0` means one
+///   per CPU core.
+///
+/// - [`TrackedFolder`] — individual folder entry with path and cleanup threshold.
+///
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct FolderConfig {
+    pub version: u32,
+    pub folders: Vec<TrackedFolder>,
+    #[serde(default = "default_quarantine_days")]
+    pub quarantine_days: u32,
+    #[serde(default)]
+    pub scan_workers: usize,
+}
+
+impl Default for FolderConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            folders: Vec::new(),
+            quarantine_days: DEFAULT_QUARANTINE_DAYS,
+            scan_workers: 0,
+        }
+    }
+}
+
+/// Returns `true`, used for boolean settings that are enabled when the config file omits them.
+fn default_true() -> bool {
+    true
+}
+
+/// Returns the default quarantine grace period, used when the config file omits it.
+fn default_quarantine_days() -> u32 {
+    DEFAULT_QUARANTINE_DAYS
+}
+
+/// Describes how [`FolderConfig::load_or_recover`] replaced a config file that could not
+/// be parsed.
+///
+/// # Fields
+///
+/// - `error`: Why the config file could not be parsed.
+/// - `backup_path`: The backup the configuration was restored from.
+/// - `corrupt_path`: Where the unparsable file was copied, so it can still be inspected.
+///
+#[derive(Debug, Clone)]
+pub struct ConfigRecovery {
+    pub error: String,
+    pub backup_path: PathBuf,
+    pub corrupt_path: PathBuf,
+}
+
+impl std::fmt::Display for ConfigRecovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the config could not be parsed ({}); restored {}, the damaged file was kept as {}",
+            self.error,
+            self.backup_path.display(),
+            self.corrupt_path.display()
+        )
+    }
+}
+
+impl FolderConfig {
+    /// Loads the folder configuration from disk, see [`FolderConfig::load_or_recover`].
+    ///
+    /// Whether a backup was restored is not returned, so callers that report a recovery
+    /// to the user call [`FolderConfig::load_or_recover`] instead.
+    ///
+    /// # Returns
+    ///
+    /// - The stored configuration, or an `Err` if it could neither be loaded nor recovered.
+    ///
+    pub fn load() -> io::Result<Self> {
+        Self::load_or_recover().map(|(config, _)| config)
+    }
+
+    /// Loads the folder configuration from disk, falling back to a backup if it is damaged.
+    ///
+    /// A file written in an older format is first copied to a backup next to it, see
+    /// [`get_config_backup_path`], then upgraded by [`migrate`] and saved in the current
+    /// format. If the upgraded file cannot be saved, the upgraded configuration is still
+    /// returned and the upgrade is repeated on the next load.
+    ///
+    /// If the file is not valid JSON or not a valid configuration, the newest backup from
+    /// [`get_config_backup_paths`] that loads is restored in its place, and the damaged file
+    /// is copied to [`get_config_corrupt_path`].
+    ///
+    /// # Returns
+    ///
+    /// - The configuration, or the default configuration if the file does not exist, and
+    ///   how it was recovered if a backup was used.
+    /// - An `Err` if the file cannot be read, was written by a newer version of
+    ///   AutoCleaner, cannot be backed up before an upgrade, or is damaged without a valid
+    ///   backup. The file is left untouched in that case, so it is never replaced by an
+    ///   empty configuration.
+    ///
+    pub fn load_or_recover() -> io::Result<(Self, Option<ConfigRecovery>)> {
+        let config_path = get_config_path();
+        if !config_path.exists() {
+            return Ok((FolderConfig::default(), None));
+        }
+
+        match read_config(&config_path) {
+            Ok((config, version)) => {
+                if version < CONFIG_VERSION {
+                    let backup_path = get_config_backup_path(version);
+                    if !backup_path.exists() {
+                        fs::copy(&config_path, &backup_path)?;
+                    }
+                    if let Err(e) = save_config(&config) {
+                        eprintln!("Failed to save the upgraded config: {}", e);
+                    }
+                }
+                Ok((config, None))
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                let Some((config, backup_path)) = get_config_backup_paths()
+                    .into_iter()
+                    .find_map(|path| Some((read_config(&path).ok()?.0, path)))
+                else {
+                    return Err(e);
+                };
+
+                let corrupt_path = get_config_corrupt_path();
+                fs::copy(&config_path, &corrupt_path)?;
+                save_config(&config)?;
+                let recovery = ConfigRecovery {
+                    error: e.to_string(),
+                    backup_path,
+                    corrupt_path,
+                };
+                Ok((config, Some(recovery)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Reads and, in memory, migrates the config file at `path`.
+///
+/// # Returns
+///
+/// - The configuration and the format version the file was written in.
+/// - An `Err` of kind `InvalidData` if the file is damaged, or of kind `Unsupported` if it
+///   was written by a newer version of AutoCleaner.
+///
+fn read_config(path: &Path) -> io::Result<(FolderConfig, u32)> {
+    let content = fs::read_to_string(path)?;
+    let mut value: Value = serde_json::from_str(&content).map_err(|e| invalid_config(path, e))?;
+    let version = file_version(&value);
+
+    migrate(&mut value).map_err(|e| {
+        let kind = if version > CONFIG_VERSION {
+            io::ErrorKind::Unsupported
+        } else {
+            io::ErrorKind::InvalidData
+        };
+        io::Error::new(kind, format!("{}: {}", path.display(), e))
+    })?;
+    let config = serde_json::from_value(value).map_err(|e| invalid_config(path, e))?;
+    Ok((config, version))
+}
+
+/// Merges a configuration changed on disk into one with unsaved local edits.
+///
+/// The merge is three-way: each folder, matched by path, and each general setting is
+/// compared against `base`. A side that left it unchanged takes the other side's value,
+/// so local and external edits to different folders or settings are both kept. When
+/// both sides changed the same folder or setting differently, the local edit wins and
+/// the conflict is reported.
+///
+/// Folders keep the local order, followed by folders only found on disk.
+///
+/// # Parameters
+///
+/// - `base`: The configuration both sides started from, i.e. as last saved or loaded.
+/// - `local`: The configuration with unsaved edits.
+/// - `remote`: The configuration now on disk.
+///
+/// # Returns
+///
+/// - The merged configuration, and the path of each conflicting folder or the name of
+///   each conflicting setting.
+///
+pub fn merge_configs(
+    base: &FolderConfig,
+    local: &FolderConfig,
+    remote: &FolderConfig,
+) -> (FolderConfig, Vec<String>) {
+    let mut conflicts = Vec::new();
+    let find =
+        |config: &FolderConfig, path: &str| config.folders.iter().find(|f| f.path == path).cloned();
+
+    let mut paths: Vec<&str> = Vec::new();
+    for folder in local
+        .folders
+        .iter()
+        .chain(&remote.folders)
+        .chain(&base.folders)
+    {
+        if !paths.contains(&folder.path.as_str()) {
+            paths.push(&folder.path);
+        }
+    }
+
+    let mut folders = Vec::new();
+    for path in paths {
+        let folder = merge_value(
+            &find(base, path),
+            &find(local, path),
+            &find(remote, path),
+            || conflicts.push(path.to_string()),
+        );
+        folders.extend(folder);
+    }
+
+    let quarantine_days = merge_value(
+        &base.quarantine_days,
+        &local.quarantine_days,
+        &remote.quarantine_days,
+        || conflicts.push("quarantine_days".to_string()),
+    );
+    let scan_workers = merge_value(
+        &base.scan_workers,
+        &local.scan_workers,
+        &remote.scan_workers,
+        || conflicts.push("scan_workers".to_string()),
+    );
+
+    let merged = FolderConfig {
+        version: local.version,
+        folders,
+        quarantine_days,
+        scan_workers,
+    };
+    (merged, conflicts)
+}
+
+/// Merges one value for [`merge_configs`], calling `on_conflict` if both sides changed
+/// it differently, in which case `local` wins.
+fn merge_value<T: PartialEq + Clone>(
+    base: &T,
+    local: &T,
+    remote: &T,
+    on_conflict: impl FnOnce(),
+) -> T {
+    if local == base || local == remote {
+        remote.clone()
+    } else {
+        if remote != base {
+            on_conflict();
+        }
+        local.clone()
+    }
+}
+
+/// Returns the format version of a parsed config file, `0` if it has none.
+fn file_version(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| version.try_into().unwrap_or(u32::MAX))
+}
+
+/// Upgrades the JSON of a config file to [`CONFIG_VERSION`] by applying each step of
+/// [`MIGRATIONS`] in turn.
+///
+/// # Parameters
+///
+/// - `value`: The parsed config file, upgraded in place.
+///
+/// # Returns
+///
+/// - An `Err` describing the failed step, or that the file is newer than this build
+///   supports.
+///
+pub fn migrate(value: &mut Value) -> Result<(), String> {
+    let version = file_version(value);
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "written by a newer version of AutoCleaner (format {}, this version reads up to {})",
+            version, CONFIG_VERSION
+        ));
+    }
+
+    for (from, step) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        step(value).map_err(|e| format!("cannot upgrade from format {}: {}", from, e))?;
+        value["version"] = Value::from(from + 1);
+    }
+    Ok(())
+}
+
+/// Upgrades an unversioned file. The only incompatibility is that `folders` was required,
+/// so a file without it is given an empty list; every other field added before versioning
+/// has a default.
+fn migrate_v0_to_v1(value: &mut Value) -> Result<(), String> {
+    let config = value.as_object_mut().ok_or("expected a JSON object")?;
+    config
+        .entry("folders")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// Creates the error returned when the config file at `path` cannot be used.
+fn invalid_config(path: &Path, error: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), error),
+    )
+}
+
+/// Retrieves user's configuration directory.
+///
+/// The returned path is used for storing application state, configuration files, and other
+/// data that should be persisted across application runs.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] representing the path to the application's data directory.
+///
+pub fn get_app_data_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("autocleaner")
+}
+
+/// Returns the path to the application's configuration file.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] representing the full path to the config file.
+///
+pub fn get_config_path() -> PathBuf {
+    let base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("autocleaner").join(CONFIG_FILE_NAME)
+}
+
+/// Returns the path the config file is copied to before it is upgraded from `version`.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] next to the config file, e.g. `tracked_folders.json.v0.bak`.
+///
+pub fn get_config_backup_path(version: u32) -> PathBuf {
+    get_app_data_dir().join(format!("{}.v{}.bak", CONFIG_FILE_NAME, version))
+}
+
+/// Returns the paths of the rolling config backups written by [`save_config`].
+///
+/// # Returns
+///
+/// - [`CONFIG_BACKUP_COUNT`] paths next to the config file, newest first, e.g.
+///   `tracked_folders.json.1.bak`. Some of them may not exist yet.
+///
+pub fn get_config_backup_paths() -> Vec<PathBuf> {
+    (1..=CONFIG_BACKUP_COUNT)
+        .map(|n| get_app_data_dir().join(format!("{}.{}.bak", CONFIG_FILE_NAME, n)))
+        .collect()
+}
+
+/// Returns the path a damaged config file is copied to when it is replaced by a backup.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] next to the config file, `tracked_folders.json.corrupt`.
+///
+pub fn get_config_corrupt_path() -> PathBuf {
+    get_app_data_dir().join(format!("{}.corrupt", CONFIG_FILE_NAME))
+}
+
+/// Saves the provided folder configuration to the configuration file.
+///
+/// The file is replaced atomically, so a crash never leaves it empty or half written.
+/// Before a valid file is replaced by a different configuration, it is kept as the
+/// newest of the rolling backups, see [`get_config_backup_paths`]. The backups are
+/// rotated at most once per [`CONFIG_BACKUP_INTERVAL`], so they keep configurations from
+/// before a session of edits. Saving an unchanged configuration does not touch the disk.
+///
+/// # Parameters
+///
+/// - `config`: A reference to the [`FolderConfig`] to be saved.
+///
+/// # Returns
+///
+/// - An `Err` if the configuration could not be written. The previous file is kept.
+///
+pub fn save_config(config: &FolderConfig) -> io::Result<()> {
+    let config_path = get_config_path();
+    let content = serde_json::to_string_pretty(config)?;
+
+    let previous = fs::read_to_string(&config_path).ok();
+    if previous.as_deref() == Some(content.as_str()) {
+        return Ok(());
+    }
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if previous.is_some() && backup_due() && read_config(&config_path).is_ok() {
+        rotate_backups(&config_path)?;
+        *LAST_BACKUP.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    }
+    write_atomic(&config_path, content.as_bytes())
+}
+
+/// Returns `true` if this process has not rotated the backups within
+/// [`CONFIG_BACKUP_INTERVAL`].
+fn backup_due() -> bool {
+    LAST_BACKUP
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_none_or(|last| last.elapsed() >= CONFIG_BACKUP_INTERVAL)
+}
+
+/// Shifts the rolling backups by one, dropping the oldest, and copies the config file at
+/// `config_path` into the newest slot.
+fn rotate_backups(config_path: &Path) -> io::Result<()> {
+    let backups = get_config_backup_paths();
+    for pair in backups.windows(2).rev() {
+        if pair[0].exists() {
+            fs::rename(&pair[0], &pair[1])?;
+        }
+    }
+    fs::copy(config_path, &backups[0])?;
+    Ok(())
+}
+
+/// Replaces the file at `path` with `content` without ever exposing a partial file.
+///
+/// The content is written to a temporary file in the same directory, flushed to disk and
+/// then renamed over `path`. Every call uses its own temporary file, so concurrent writes
+/// from several threads or processes do not interfere. On failure the temporary file is removed and `path` keeps its
+/// previous content.
+///
+/// # Parameters
+///
+/// - `path`: The file to replace.
+/// - `content`: The new content of the file.
+///
+/// # Returns
+///
+/// - An `Err` if the temporary file could not be written or renamed.
+///
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = write_synced(&temp_path, content).and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    // Persist the rename itself. Directories cannot be opened as files on Windows.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Creates or truncates `path`, writes `content` and waits until it reached the disk.
+fn write_synced(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
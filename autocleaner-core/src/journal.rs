@@ -0,0 +1,531 @@
+use crate::{
+    cleanup::{CleanupPlan, CleanupReport, PlannedFile},
+    config::{get_app_data_dir, DeleteMode},
+    undo::UndoReport,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The file name of the journal currently being appended to.
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+
+/// The size in bytes above which the journal is rotated before the next run is recorded.
+const MAX_JOURNAL_SIZE: u64 = 10 * 1024 * 1024;
+
+/// How many rotated journal files are kept besides the current one.
+const MAX_ROTATED_JOURNALS: usize = 4;
+
+/// Distinguishes runs started within the same millisecond by this process.
+static RUN_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The kind of operation a journal entry belongs to.
+///
+/// # Variants
+///
+/// - `Scan`: A dry-run scan that listed files to remove.
+/// - `Cleanup`: A cleanup that removed files.
+/// - `Undo`: Files of an earlier cleanup were restored from the trash or quarantine.
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunKind {
+    Scan,
+    Cleanup,
+    Undo,
+}
+
+impl std::fmt::Display for RunKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunKind::Scan => write!(f, "Scan"),
+            RunKind::Cleanup => write!(f, "Cleanup"),
+            RunKind::Undo => write!(f, "Undo"),
+        }
+    }
+}
+
+/// What happened to the file of a journal entry.
+///
+/// # Variants
+///
+/// - `Planned`: A scan selected the file for removal.
+/// - `Deleted`: A cleanup removed the file.
+/// - `Skipped`: A cleanup left the file in place because it changed since the scan.
+/// - `Failed`: The file could not be scanned, removed or restored.
+/// - `Restored`: An undo moved the file back to its original path.
+/// - `Conflict`: An undo left the file in the trash or quarantine, because a file has
+///   since been created at its original path.
+/// - `Empty`: The run touched no file. The entry only records that the run happened, and
+///   its path is the tracked folder.
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Planned,
+    Deleted,
+    Skipped,
+    Failed,
+    Restored,
+    Conflict,
+    Empty,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Planned => write!(f, "planned"),
+            Outcome::Deleted => write!(f, "deleted"),
+            Outcome::Skipped => write!(f, "skipped"),
+            Outcome::Failed => write!(f, "failed"),
+            Outcome::Restored => write!(f, "restored"),
+            Outcome::Conflict => write!(f, "conflict"),
+            Outcome::Empty => write!(f, "empty"),
+        }
+    }
+}
+
+/// A single line of the journal, describing one file of a scan or cleanup.
+///
+/// # Fields
+///
+/// - `timestamp`: When the entry was recorded.
+/// - `run`: The id shared by every entry of the same scan or cleanup.
+/// - `kind`: Whether the entry belongs to a scan or a cleanup.
+/// - `folder`: The tracked folder that was scanned or cleaned.
+/// - `path`: The affected file.
+/// - `size`: The file's size in bytes, if known.
+/// - `modified`: The file's modified time, if known.
+/// - `rule`: The rule that selected the file, if any.
+/// - `delete_mode`: How the folder disposes of removed files.
+/// - `outcome`: What happened to the file.
+/// - `location`: Where a removed file was moved to in the trash or quarantine, if known.
+/// - `message`: Why the file was skipped or failed.
+/// - `reverts`: For undo entries, the id of the cleanup run that was undone.
+///
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    pub run: String,
+    pub kind: RunKind,
+    pub folder: String,
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    pub delete_mode: DeleteMode,
+    pub outcome: Outcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverts: Option<String>,
+}
+
+/// All journal entries of one scan or cleanup.
+///
+/// # Fields
+///
+/// - `id`: The run id shared by the entries.
+/// - `kind`: Whether the run was a scan, a cleanup or an undo.
+/// - `folder`: The tracked folder of the run.
+/// - `timestamp`: When the first entry of the run was recorded.
+/// - `entries`: The run's entries, in the order they were recorded.
+/// - `restored`: The files of this cleanup that a later undo run restored.
+/// - `reverted`: Whether later undo runs restored every file of this cleanup that can be
+///   restored.
+///
+#[derive(Clone, Debug)]
+pub struct JournalRun {
+    pub id: String,
+    pub kind: RunKind,
+    pub folder: String,
+    pub timestamp: DateTime<Utc>,
+    pub entries: Vec<JournalEntry>,
+    pub restored: HashSet<PathBuf>,
+    pub reverted: bool,
+}
+
+impl JournalRun {
+    /// Returns the entries of files this cleanup moved to a known location in the trash or
+    /// quarantine that no undo has restored yet.
+    pub fn restorable(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().filter(|e| {
+            e.outcome == Outcome::Deleted
+                && e.delete_mode != DeleteMode::Permanent
+                && e.location.is_some()
+                && !self.restored.contains(&e.path)
+        })
+    }
+
+    /// Returns how many entries of the run have the given outcome.
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.entries.iter().filter(|e| e.outcome == outcome).count()
+    }
+
+    /// Returns a one-line summary of the run, e.g. `3 deleted, 1 skipped, 0 failed`.
+    pub fn summary(&self) -> String {
+        match self.kind {
+            RunKind::Scan => format!(
+                "{} planned, {} failed",
+                self.count(Outcome::Planned),
+                self.count(Outcome::Failed)
+            ),
+            RunKind::Cleanup => format!(
+                "{} deleted, {} skipped, {} failed",
+                self.count(Outcome::Deleted),
+                self.count(Outcome::Skipped),
+                self.count(Outcome::Failed)
+            ),
+            RunKind::Undo => format!(
+                "{} restored, {} conflicts, {} failed",
+                self.count(Outcome::Restored),
+                self.count(Outcome::Conflict),
+                self.count(Outcome::Failed)
+            ),
+        }
+    }
+}
+
+/// Returns the path to the journal currently being appended to.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] pointing to `journal.jsonl` inside the application's data directory.
+///
+pub fn get_journal_path() -> PathBuf {
+    get_app_data_dir().join(JOURNAL_FILE_NAME)
+}
+
+/// Returns the path of the `index`-th rotated journal, where `1` is the most recent.
+fn rotated_journal_path(index: usize) -> PathBuf {
+    get_app_data_dir().join(format!("journal.{}.jsonl", index))
+}
+
+/// Creates a new, unique run id from the current time.
+fn new_run_id() -> String {
+    format!(
+        "{}-{}-{}",
+        Utc::now().format("%Y%m%dT%H%M%S%3f"),
+        std::process::id(),
+        RUN_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Creates an entry for a planned file, with the outcome still to be filled in.
+fn file_entry(run: &str, kind: RunKind, plan: &CleanupPlan, file: &PlannedFile) -> JournalEntry {
+    JournalEntry {
+        timestamp: Utc::now(),
+        run: run.to_string(),
+        kind,
+        folder: plan.root.clone(),
+        path: file.path.clone(),
+        size: Some(file.size),
+        modified: Some(file.modified),
+        rule: Some(file.rule.to_string()),
+        delete_mode: plan.delete_mode,
+        outcome: Outcome::Planned,
+        location: None,
+        message: None,
+        reverts: None,
+    }
+}
+
+/// Creates an entry for a path that failed without a planned file.
+fn failure_entry(
+    run: &str,
+    kind: RunKind,
+    plan: &CleanupPlan,
+    path: &Path,
+    message: String,
+) -> JournalEntry {
+    JournalEntry {
+        timestamp: Utc::now(),
+        run: run.to_string(),
+        kind,
+        folder: plan.root.clone(),
+        path: path.to_path_buf(),
+        size: None,
+        modified: None,
+        rule: None,
+        delete_mode: plan.delete_mode,
+        outcome: Outcome::Failed,
+        location: None,
+        message: Some(message),
+        reverts: None,
+    }
+}
+
+/// Creates the entry recording a run that touched no file.
+fn empty_run_entry(run: &str, kind: RunKind, plan: &CleanupPlan) -> JournalEntry {
+    JournalEntry {
+        timestamp: Utc::now(),
+        run: run.to_string(),
+        kind,
+        folder: plan.root.clone(),
+        path: PathBuf::from(&plan.root),
+        size: None,
+        modified: None,
+        rule: None,
+        delete_mode: plan.delete_mode,
+        outcome: Outcome::Empty,
+        location: None,
+        message: None,
+        reverts: None,
+    }
+}
+
+/// Records every planned file and scan error of a finished scan.
+///
+/// Cancelled scans are not recorded. A scan that found nothing is recorded as a run
+/// without entries.
+///
+/// # Returns
+///
+/// - An `Err` if the journal could not be written.
+///
+pub fn record_scan(plan: &CleanupPlan) -> io::Result<()> {
+    if plan.cancelled {
+        return Ok(());
+    }
+
+    let run = new_run_id();
+    let files = plan
+        .files
+        .iter()
+        .map(|file| file_entry(&run, RunKind::Scan, plan, file));
+    let errors = plan
+        .errors
+        .iter()
+        .map(|error| failure_entry(&run, RunKind::Scan, plan, &error.path, error.to_string()));
+    append_run(files.chain(errors), || {
+        empty_run_entry(&run, RunKind::Scan, plan)
+    })
+}
+
+/// Records the outcome of every file processed by a cleanup.
+///
+/// A cleanup that processed no file is recorded as a run without entries.
+///
+/// # Parameters
+///
+/// - `plan`: The plan that was executed.
+/// - `report`: The [`CleanupReport`] returned for it.
+///
+/// # Returns
+///
+/// - An `Err` if the journal could not be written.
+///
+pub fn record_cleanup(plan: &CleanupPlan, report: &CleanupReport) -> io::Result<()> {
+    let run = new_run_id();
+    let planned: HashMap<_, _> = plan.files.iter().map(|f| (&f.path, f)).collect();
+
+    let deleted = report.deleted.iter().map(|removed| JournalEntry {
+        outcome: Outcome::Deleted,
+        location: removed.location.clone(),
+        ..file_entry(&run, RunKind::Cleanup, plan, &removed.file)
+    });
+    let skipped = report.skipped.iter().map(|skipped| JournalEntry {
+        outcome: Outcome::Skipped,
+        message: Some(skipped.reason.clone()),
+        ..file_entry(&run, RunKind::Cleanup, plan, &skipped.file)
+    });
+    let failed = report
+        .failed
+        .iter()
+        .map(|error| match planned.get(&error.path) {
+            Some(file) => JournalEntry {
+                outcome: Outcome::Failed,
+                message: Some(format!("{}: {}", error.kind, error.message)),
+                ..file_entry(&run, RunKind::Cleanup, plan, file)
+            },
+            None => failure_entry(&run, RunKind::Cleanup, plan, &error.path, error.to_string()),
+        });
+
+    append_run(deleted.chain(skipped).chain(failed), || {
+        empty_run_entry(&run, RunKind::Cleanup, plan)
+    })
+}
+
+/// Records the outcome of undoing a cleanup run, which marks the restored files of that
+/// run as reverted. Files left behind by a conflict or failure can be undone again.
+///
+/// # Parameters
+///
+/// - `run`: The cleanup run that was undone.
+/// - `report`: The [`UndoReport`] returned for it.
+///
+/// # Returns
+///
+/// - An `Err` if the journal could not be written.
+///
+pub fn record_undo(run: &JournalRun, report: &UndoReport) -> io::Result<()> {
+    let id = new_run_id();
+    let deleted: HashMap<_, _> = run
+        .entries
+        .iter()
+        .filter(|e| e.outcome == Outcome::Deleted)
+        .map(|e| (e.path.as_path(), e))
+        .collect();
+
+    let entry = |path: &Path, outcome: Outcome, message: Option<String>| {
+        let original = deleted.get(path);
+        JournalEntry {
+            timestamp: Utc::now(),
+            run: id.clone(),
+            kind: RunKind::Undo,
+            folder: run.folder.clone(),
+            path: path.to_path_buf(),
+            size: original.and_then(|e| e.size),
+            modified: original.and_then(|e| e.modified),
+            rule: original.and_then(|e| e.rule.clone()),
+            delete_mode: original.map_or_else(DeleteMode::default, |e| e.delete_mode),
+            outcome,
+            location: original.and_then(|e| e.location.clone()),
+            message,
+            reverts: Some(run.id.clone()),
+        }
+    };
+
+    let restored = report
+        .restored
+        .iter()
+        .map(|path| entry(path, Outcome::Restored, None));
+    let conflicts = report.conflicts.iter().map(|path| {
+        entry(
+            path,
+            Outcome::Conflict,
+            Some("a file has been created at the original path".to_string()),
+        )
+    });
+    let failed = report.failed.iter().map(|error| {
+        entry(
+            &error.path,
+            Outcome::Failed,
+            Some(format!("{}: {}", error.kind, error.message)),
+        )
+    });
+
+    append(restored.chain(conflicts).chain(failed))
+}
+
+/// Appends the entries of a run to the journal, or the entry `empty` creates if there
+/// are none, so every run is recorded.
+fn append_run(
+    entries: impl Iterator<Item = JournalEntry>,
+    empty: impl FnOnce() -> JournalEntry,
+) -> io::Result<()> {
+    let mut entries = entries.peekable();
+    if entries.peek().is_none() {
+        return append([empty()].into_iter());
+    }
+    append(entries)
+}
+
+/// Appends entries to the journal, rotating it first if it grew too large.
+fn append(entries: impl Iterator<Item = JournalEntry>) -> io::Result<()> {
+    let path = get_journal_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_JOURNAL_SIZE) {
+        rotate(&path)?;
+    }
+
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let mut writer = BufWriter::new(file);
+    for entry in entries {
+        serde_json::to_writer(&mut writer, &entry)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Shifts `journal.N.jsonl` to `journal.N+1.jsonl` and the current journal to
+/// `journal.1.jsonl`, dropping the oldest rotated journal.
+fn rotate(path: &Path) -> io::Result<()> {
+    let _ = fs::remove_file(rotated_journal_path(MAX_ROTATED_JOURNALS));
+    for index in (1..MAX_ROTATED_JOURNALS).rev() {
+        let from = rotated_journal_path(index);
+        if from.exists() {
+            fs::rename(&from, rotated_journal_path(index + 1))?;
+        }
+    }
+    fs::rename(path, rotated_journal_path(1))
+}
+
+/// Reads every entry of the current and rotated journals, oldest first.
+///
+/// Lines that cannot be parsed are skipped.
+///
+pub fn read_journal() -> Vec<JournalEntry> {
+    let rotated = (1..=MAX_ROTATED_JOURNALS).rev().map(rotated_journal_path);
+    rotated
+        .chain([get_journal_path()])
+        .filter_map(|path| File::open(path).ok())
+        .flat_map(|file| BufReader::new(file).lines().map_while(Result::ok))
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+/// Groups the journal's entries by run. Runs that touched no file have no entries.
+///
+/// # Parameters
+///
+/// - `limit`: The maximum number of runs to return.
+///
+/// # Returns
+///
+/// The most recent runs, newest first.
+///
+pub fn load_runs(limit: usize) -> Vec<JournalRun> {
+    let mut runs: Vec<JournalRun> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut restored: HashMap<String, HashSet<PathBuf>> = HashMap::new();
+
+    for entry in read_journal() {
+        if let Some(id) = &entry.reverts {
+            if entry.outcome == Outcome::Restored {
+                restored
+                    .entry(id.clone())
+                    .or_default()
+                    .insert(entry.path.clone());
+            }
+        }
+        let i = *index.entry(entry.run.clone()).or_insert_with(|| {
+            runs.push(JournalRun {
+                id: entry.run.clone(),
+                kind: entry.kind,
+                folder: entry.folder.clone(),
+                timestamp: entry.timestamp,
+                entries: Vec::new(),
+                restored: HashSet::new(),
+                reverted: false,
+            });
+            runs.len() - 1
+        });
+        // The entry of a run that touched no file only creates the run.
+        if entry.outcome != Outcome::Empty {
+            runs[i].entries.push(entry);
+        }
+    }
+
+    for run in &mut runs {
+        run.restored = restored.remove(&run.id).unwrap_or_default();
+        run.reverted = !run.restored.is_empty() && run.restorable().next().is_none();
+    }
+
+    runs.reverse();
+    runs.truncate(limit);
+    runs
+}
@@ -0,0 +1,31 @@
+//! The platform-independent engine of AutoCleaner.
+//!
+//! This crate scans tracked folders for files to remove, executes the resulting
+//! [`cleanup::CleanupPlan`]s and keeps the configuration, quarantine and journal on disk.
+//! It has no GUI or desktop dependencies, so it builds on every platform and is shared by
+//! the `autocleaner` binary's GUI, tray icon and command line.
+//!
+//! # Modules
+//!
+//! - [`config`]: The tracked folders and their cleanup rules, stored as JSON.
+//! - [`settings`]: General application settings, such as the executable's path.
+//! - [`cleanup`]: Scanning folders into plans and executing them.
+//! - [`walker`]: The serial and parallel directory walks used by scans.
+//! - [`progress`]: Counters shared with the thread displaying a running scan or cleanup.
+//! - [`trash`]: Moving files to and restoring them from the platform's trash.
+//! - [`quarantine`]: The application's own quarantine for removed files.
+//! - [`journal`]: The log of every scan, cleanup and undo.
+//! - [`undo`]: Restoring the files of a past cleanup.
+//! - [`schedule`]: When the daemon evaluates each folder, and the persisted run times.
+//!
+
+pub mod cleanup;
+pub mod config;
+pub mod journal;
+pub mod progress;
+pub mod quarantine;
+pub mod schedule;
+pub mod settings;
+pub mod trash;
+pub mod undo;
+pub mod walker;
@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Counters shared between a running scan or cleanup and the thread displaying it.
+///
+/// The worker updates the counters while it runs, and any other thread may read them or
+/// request cancellation at the same time.
+///
+/// # Fields
+///
+/// - `visited`: Files visited so far. For a cleanup, the planned files processed so far.
+/// - `matched`: Files selected for removal so far. For a cleanup, the files removed so far.
+/// - `bytes`: The combined size of the `matched` files.
+/// - `cancelled`: Set once cancellation was requested.
+///
+#[derive(Default, Debug)]
+pub struct Progress {
+    visited: AtomicUsize,
+    matched: AtomicUsize,
+    bytes: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+/// A point-in-time copy of the counters of a [`Progress`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct ProgressSnapshot {
+    pub visited: usize,
+    pub matched: usize,
+    pub bytes: u64,
+}
+
+impl Progress {
+    /// Records that a file was visited.
+    pub fn add_visited(&self) {
+        self.visited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a file of `size` bytes was matched.
+    pub fn add_match(&self, size: u64) {
+        self.matched.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    /// Replaces the match counters with the final values of a finished scan.
+    pub fn set_matches(&self, matched: usize, bytes: u64) {
+        self.matched.store(matched, Ordering::Relaxed);
+        self.bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Returns the current values of the counters.
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            visited: self.visited.load(Ordering::Relaxed),
+            matched: self.matched.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Asks the running scan or cleanup to stop as soon as possible.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once [`Progress::cancel`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,353 @@
+use crate::config::{get_app_data_dir, write_atomic};
+use chrono::{DateTime, Duration, Utc};
+use fs4::FileExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// The name of the directory under [`get_app_data_dir()`] that holds quarantined files.
+const QUARANTINE_DIR_NAME: &str = "quarantine";
+
+/// The file name of the manifest recording every quarantined file.
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// The file name of the lock held while the manifest is read, changed and written back.
+const MANIFEST_LOCK_FILE_NAME: &str = "manifest.lock";
+
+/// A single file that was moved into quarantine.
+///
+/// # Fields
+///
+/// - `original_path`: Where the file lived before it was quarantined.
+/// - `stored_path`: Where the file is kept inside the quarantine directory.
+/// - `quarantined_at`: When the file was moved into quarantine.
+/// - `size`: The size of the file in bytes.
+///
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QuarantineEntry {
+    pub original_path: PathBuf,
+    pub stored_path: PathBuf,
+    pub quarantined_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// The list of all files currently held in quarantine.
+///
+/// # Fields
+///
+/// - `entries`: Every quarantined file, oldest first.
+///
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct QuarantineManifest {
+    pub entries: Vec<QuarantineEntry>,
+}
+
+impl QuarantineManifest {
+    /// Loads the quarantine manifest from disk.
+    ///
+    /// # Returns
+    ///
+    /// - The stored manifest, or an empty one if it does not exist.
+    /// - An `Err` if it cannot be read or parsed. The file is left untouched, since
+    ///   replacing it would lose track of every file quarantined so far.
+    ///
+    pub fn load() -> io::Result<Self> {
+        let manifest_path = get_manifest_path();
+        let content = match fs::read_to_string(&manifest_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", manifest_path.display(), e),
+            )
+        })
+    }
+
+    /// Writes the manifest to disk atomically, creating the quarantine directory if needed.
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(get_quarantine_dir())?;
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomic(&get_manifest_path(), json.as_bytes())
+    }
+}
+
+/// Returns the directory that holds quarantined files.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] pointing to `quarantine` inside the application's data directory.
+///
+pub fn get_quarantine_dir() -> PathBuf {
+    get_app_data_dir().join(QUARANTINE_DIR_NAME)
+}
+
+/// Returns the path to the quarantine manifest file.
+fn get_manifest_path() -> PathBuf {
+    get_quarantine_dir().join(MANIFEST_FILE_NAME)
+}
+
+/// Waits for the exclusive lock on the quarantine manifest.
+///
+/// The GUI, tray, daemon and command line may change the manifest at the same time. Each
+/// of them holds this lock from loading the manifest until saving it, so no change is
+/// lost to a concurrent write.
+///
+/// # Returns
+///
+/// - The open lock file. The lock is released when it is dropped.
+///
+fn lock_manifest() -> io::Result<File> {
+    let quarantine_dir = get_quarantine_dir();
+    fs::create_dir_all(&quarantine_dir)?;
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(quarantine_dir.join(MANIFEST_LOCK_FILE_NAME))?;
+    file.lock_exclusive()?;
+    Ok(file)
+}
+
+/// A group of files quarantined by a single cleanup of one tracked folder.
+///
+/// Every batch gets its own directory inside the quarantine, and each file keeps its
+/// path relative to the tracked folder inside it. The manifest is only updated once,
+/// when the batch is committed, but it is checked to load before the first file is moved,
+/// so files are not quarantined without a manifest to record them in.
+///
+/// # Fields
+///
+/// - `root`: The tracked folder the files are removed from.
+/// - `dir`: The batch directory, created lazily when the first file is added.
+/// - `entries`: The files moved so far.
+///
+pub struct QuarantineBatch {
+    root: PathBuf,
+    dir: Option<PathBuf>,
+    entries: Vec<QuarantineEntry>,
+}
+
+impl QuarantineBatch {
+    /// Starts a new batch for files removed from the tracked folder `root`.
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: PathBuf::from(root),
+            dir: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Moves a file into the batch, keeping its path relative to the tracked folder.
+    ///
+    /// # Parameters
+    ///
+    /// - `path`: The file to quarantine.
+    ///
+    /// # Returns
+    ///
+    /// - The file's location inside the quarantine, or an `Err` if it could not be moved
+    ///   or the manifest cannot be loaded.
+    ///
+    pub fn add(&mut self, path: &Path) -> io::Result<PathBuf> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .ok()
+            .filter(|p| !p.as_os_str().is_empty())
+            .or_else(|| path.file_name().map(Path::new))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?
+            .to_path_buf();
+
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => {
+                QuarantineManifest::load()?;
+                let dir = create_batch_dir()?;
+                self.dir = Some(dir.clone());
+                dir
+            }
+        };
+
+        let size = fs::metadata(path)?.len();
+        let stored_path = dir.join(relative);
+        move_file(path, &stored_path)?;
+
+        self.entries.push(QuarantineEntry {
+            original_path: path.to_path_buf(),
+            stored_path: stored_path.clone(),
+            quarantined_at: Utc::now(),
+            size,
+        });
+        Ok(stored_path)
+    }
+
+    /// Records every file of the batch in the quarantine manifest.
+    pub fn commit(self) -> io::Result<()> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+
+        let _lock = lock_manifest()?;
+        let mut manifest = QuarantineManifest::load()?;
+        manifest.entries.extend(self.entries);
+        manifest.save()
+    }
+}
+
+/// Creates a fresh, uniquely named batch directory inside the quarantine.
+fn create_batch_dir() -> io::Result<PathBuf> {
+    let quarantine_dir = get_quarantine_dir();
+    fs::create_dir_all(&quarantine_dir)?;
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S").to_string();
+    for attempt in 0u32.. {
+        let dir = quarantine_dir.join(format!("{}-{}", stamp, attempt));
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("batch directory candidates are unbounded")
+}
+
+/// Moves a file, creating missing parent directories and copying across filesystems.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(from, to)?;
+    fs::remove_file(from).inspect_err(|_| {
+        let _ = fs::remove_file(to);
+    })
+}
+
+/// Restores a quarantined file to its original location.
+///
+/// # Parameters
+///
+/// - `stored_path`: The file's location inside the quarantine.
+///
+/// # Returns
+///
+/// - The path the file was restored to.
+/// - An `Err` of kind `NotFound` if no quarantined file is stored at `stored_path`, or of
+///   kind `AlreadyExists` if a file has since been created at the original location.
+///
+pub fn restore(stored_path: &Path) -> io::Result<PathBuf> {
+    let _lock = lock_manifest()?;
+    let manifest = QuarantineManifest::load()?;
+    let index = manifest
+        .entries
+        .iter()
+        .position(|e| e.stored_path == stored_path);
+    restore_entry(manifest, index, stored_path)
+}
+
+/// Restores the most recently quarantined file that originally lived at `original_path`.
+///
+/// # Returns
+///
+/// - The path the file was restored to, or an `Err` as described for [`restore`].
+///
+pub fn restore_original(original_path: &Path) -> io::Result<PathBuf> {
+    let _lock = lock_manifest()?;
+    let manifest = QuarantineManifest::load()?;
+    let index = manifest
+        .entries
+        .iter()
+        .rposition(|e| e.original_path == original_path);
+    restore_entry(manifest, index, original_path)
+}
+
+/// Moves the manifest entry at `index` back to its original path and saves the manifest.
+///
+/// The caller holds the manifest lock.
+///
+fn restore_entry(
+    mut manifest: QuarantineManifest,
+    index: Option<usize>,
+    requested: &Path,
+) -> io::Result<PathBuf> {
+    let index = index.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not in quarantine", requested.display()),
+        )
+    })?;
+
+    let entry = &manifest.entries[index];
+    if entry.original_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", entry.original_path.display()),
+        ));
+    }
+
+    move_file(&entry.stored_path, &entry.original_path)?;
+    let entry = manifest.entries.remove(index);
+    manifest.save()?;
+    Ok(entry.original_path)
+}
+
+/// Permanently deletes quarantined files whose grace period has passed.
+///
+/// # Parameters
+///
+/// - `grace_days`: How many days a file stays in quarantine before it is purged.
+///
+/// # Returns
+///
+/// - The number of files that were permanently deleted.
+///
+pub fn purge_expired(grace_days: u32) -> io::Result<usize> {
+    let threshold = Utc::now() - Duration::days(grace_days.into());
+    let _lock = lock_manifest()?;
+    let mut manifest = QuarantineManifest::load()?;
+    let original_len = manifest.entries.len();
+    let mut purged = 0;
+
+    manifest.entries.retain(|entry| {
+        if entry.quarantined_at >= threshold {
+            return true;
+        }
+        match fs::remove_file(&entry.stored_path) {
+            Ok(()) => {
+                purged += 1;
+                false
+            }
+            Err(e) => e.kind() != io::ErrorKind::NotFound,
+        }
+    });
+
+    if manifest.entries.len() != original_len {
+        remove_empty_dirs(&get_quarantine_dir());
+        manifest.save()?;
+    }
+    Ok(purged)
+}
+
+/// Removes empty directories below `root`, deepest first, keeping `root` itself.
+fn remove_empty_dirs(root: &Path) {
+    for entry in WalkDir::new(root)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+    {
+        let _ = fs::remove_dir(entry.path());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::config::{get_app_data_dir, write_atomic, TrackedFolder};
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf, str::FromStr};
+
+/// The file under [`get_app_data_dir()`] recording when each folder last ran and runs next.
+const STATE_FILE_NAME: &str = "schedule.json";
+
+/// When the daemon evaluates a tracked folder.
+///
+/// # Variants
+///
+/// - `EveryHours`: Every given number of hours after the previous run.
+/// - `Daily`: Every day at the given local time.
+/// - `Cron`: At the times matched by a cron expression in local time. Both the standard
+///   five fields (`minute hour day month weekday`) and a leading seconds field are accepted.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    EveryHours(u32),
+    Daily(NaiveTime),
+    Cron(String),
+}
+
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schedule::EveryHours(1) => write!(f, "every hour"),
+            Schedule::EveryHours(hours) => write!(f, "every {} hours", hours),
+            Schedule::Daily(time) => write!(f, "daily at {}", time.format("%H:%M")),
+            Schedule::Cron(expression) => write!(f, "cron \"{}\"", expression),
+        }
+    }
+}
+
+impl Schedule {
+    /// Returns the first time after `after` at which the schedule fires.
+    ///
+    /// # Returns
+    ///
+    /// - The next run time, or an `Err` describing why the schedule is invalid, e.g. a
+    ///   cron expression that does not parse or never matches.
+    ///
+    pub fn next_after(&self, after: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        match self {
+            Schedule::EveryHours(hours) => Ok(after + Duration::hours((*hours).max(1).into())),
+            Schedule::Daily(time) => {
+                let local = after.with_timezone(&Local);
+                // Skip days on which the time does not exist, e.g. when clocks go forward.
+                (0..=2)
+                    .filter_map(|days| {
+                        let date = local.date_naive() + Duration::days(days);
+                        Local.from_local_datetime(&date.and_time(*time)).earliest()
+                    })
+                    .map(|next| next.with_timezone(&Utc))
+                    .find(|&next| next > after)
+                    .ok_or_else(|| format!("{} does not occur in the next days", time))
+            }
+            Schedule::Cron(expression) => parse_cron(expression)?
+                .after(&after.with_timezone(&Local))
+                .next()
+                .map(|next| next.with_timezone(&Utc))
+                .ok_or_else(|| format!("cron \"{}\" never matches again", expression)),
+        }
+    }
+
+    /// Checks that the schedule can compute run times.
+    ///
+    /// # Returns
+    ///
+    /// - An `Err` as described for [`Schedule::next_after`].
+    ///
+    pub fn validate(&self) -> Result<(), String> {
+        self.next_after(Utc::now()).map(|_| ())
+    }
+}
+
+/// Parses a cron expression, adding a seconds field to standard five-field expressions.
+fn parse_cron(expression: &str) -> Result<cron::Schedule, String> {
+    let expression = expression.trim();
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+
+    cron::Schedule::from_str(&normalized)
+        .map_err(|e| format!("invalid cron \"{}\": {}", expression, e))
+}
+
+/// The persisted run times of one scheduled folder.
+///
+/// # Fields
+///
+/// - `schedule`: The schedule `next_run` was computed from. When the folder's schedule
+///   changes, `next_run` is computed again.
+/// - `last_run`: When the folder was last evaluated, if ever.
+/// - `next_run`: When the folder is evaluated next.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FolderSchedule {
+    pub schedule: Schedule,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: DateTime<Utc>,
+}
+
+/// The run times of every scheduled folder, persisted so runs missed while the daemon was
+/// not running (e.g. across a reboot) are caught up when it starts again.
+///
+/// # Fields
+///
+/// - `folders`: The run times of each scheduled folder, keyed by folder path.
+///
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct ScheduleState {
+    pub folders: BTreeMap<String, FolderSchedule>,
+}
+
+impl ScheduleState {
+    /// Loads the schedule state from disk.
+    ///
+    /// # Returns
+    ///
+    /// - The stored state, or an empty one if it does not exist.
+    /// - An `Err` if it cannot be read or parsed. The file is left untouched, since
+    ///   replacing it would lose the time every folder last ran.
+    ///
+    pub fn load() -> io::Result<Self> {
+        let state_path = get_schedule_state_path();
+        let content = match fs::read_to_string(&state_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", state_path.display(), e),
+            )
+        })
+    }
+
+    /// Writes the state to disk atomically, creating the application's data directory if
+    /// needed.
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(get_app_data_dir())?;
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomic(&get_schedule_state_path(), json.as_bytes())
+    }
+
+    /// Brings the state in line with the tracked folders.
+    ///
+    /// Folders that are no longer tracked or scheduled are dropped. Newly scheduled folders
+    /// first run at their schedule's next time after `now`. When a folder's schedule
+    /// changed, its next run is computed again from its last run, so it is due right away
+    /// if the new schedule would already have fired since.
+    ///
+    /// # Parameters
+    ///
+    /// - `folders`: The tracked folders.
+    /// - `now`: The current time.
+    ///
+    /// # Returns
+    ///
+    /// - The path and error of every folder whose schedule is invalid. These folders are
+    ///   not scheduled.
+    ///
+    pub fn sync(&mut self, folders: &[TrackedFolder], now: DateTime<Utc>) -> Vec<(String, String)> {
+        let mut errors = Vec::new();
+        let mut synced = BTreeMap::new();
+
+        for folder in folders {
+            let Some(schedule) = &folder.schedule else {
+                continue;
+            };
+            let previous = self.folders.remove(&folder.path);
+            if let Some(previous) = previous.as_ref().filter(|p| &p.schedule == schedule) {
+                synced.insert(folder.path.clone(), previous.clone());
+                continue;
+            }
+
+            let last_run = previous.and_then(|p| p.last_run);
+            match schedule.next_after(last_run.unwrap_or(now)) {
+                Ok(next_run) => {
+                    synced.insert(
+                        folder.path.clone(),
+                        FolderSchedule {
+                            schedule: schedule.clone(),
+                            last_run,
+                            next_run,
+                        },
+                    );
+                }
+                Err(e) => errors.push((folder.path.clone(), e)),
+            }
+        }
+
+        self.folders = synced;
+        errors
+    }
+
+    /// Returns the paths of the folders whose next run is at or before `now`.
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<String> {
+        self.folders
+            .iter()
+            .filter(|(_, folder)| folder.next_run <= now)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Records that the folder at `path` ran at `now` and schedules its next run.
+    ///
+    /// A missed run is only caught up once: the next run is computed from `now`, not from
+    /// the run that was missed.
+    ///
+    pub fn mark_run(&mut self, path: &str, now: DateTime<Utc>) {
+        let Some(folder) = self.folders.get_mut(path) else {
+            return;
+        };
+        folder.last_run = Some(now);
+        match folder.schedule.next_after(now) {
+            Ok(next_run) => folder.next_run = next_run,
+            Err(_) => {
+                self.folders.remove(path);
+            }
+        }
+    }
+
+    /// Returns the earliest next run of all scheduled folders, if any folder is scheduled.
+    pub fn next_wakeup(&self) -> Option<DateTime<Utc>> {
+        self.folders.values().map(|folder| folder.next_run).min()
+    }
+}
+
+/// Returns the path to the file storing the [`ScheduleState`].
+///
+/// # Returns
+///
+/// - A [`PathBuf`] pointing to `schedule.json` inside the application's data directory.
+///
+pub fn get_schedule_state_path() -> PathBuf {
+    get_app_data_dir().join(STATE_FILE_NAME)
+}
@@ -0,0 +1,451 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Moves a file to the user's trash instead of deleting it permanently.
+///
+/// On Linux this implements the
+/// [freedesktop.org Trash specification](https://specifications.freedesktop.org/trash-spec/trashspec-latest.html):
+/// files on the home filesystem go to `$XDG_DATA_HOME/Trash`, files on other mounts go to
+/// the mount's `.Trash/$uid` or `.Trash-$uid` directory. On Windows the file is sent to
+/// the Recycle Bin. Other platforms are not supported.
+///
+/// # Parameters
+///
+/// - `path`: The file to move to the trash.
+///
+/// # Returns
+///
+/// - `Ok(Some(PathBuf))` with the file's new location inside the trash, when it is known.
+/// - `Ok(None)` if the file was trashed but its new location could not be determined.
+/// - `Err` if the file could not be trashed. The original file is left in place.
+///
+pub fn move_to_trash(path: &Path) -> io::Result<Option<PathBuf>> {
+    platform::move_to_trash(path)
+}
+
+/// Moves a file from the trash back to where it was trashed from.
+///
+/// The trash's bookkeeping for the file (the `.trashinfo` file, or the Recycle Bin's
+/// `$I` file) is removed as well.
+///
+/// # Parameters
+///
+/// - `location`: The file's location inside the trash, as returned by [`move_to_trash`].
+/// - `original`: The path the file had before it was trashed.
+///
+/// # Returns
+///
+/// - An `Err` of kind `AlreadyExists` if a file has since been created at `original`,
+///   `Unsupported` on other platforms, or any other error if the file could not be moved.
+///   The trashed file is left in place.
+///
+pub fn restore_from_trash(location: &Path, original: &Path) -> io::Result<()> {
+    if original.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", original.display()),
+        ));
+    }
+    if let Some(parent) = original.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    platform::restore_from_trash(location, original)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use chrono::Local;
+    use std::{
+        ffi::OsString,
+        fs::{self, DirBuilder, File, OpenOptions},
+        io::{self, Write},
+        os::unix::{
+            ffi::OsStrExt,
+            fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        },
+        path::{Path, PathBuf},
+    };
+
+    /// The sticky bit, required on a shared `$topdir/.Trash` directory.
+    const STICKY_BIT: u32 = 0o1000;
+
+    /// A trash directory together with the way its `.trashinfo` files record paths.
+    ///
+    /// # Fields
+    ///
+    /// - `dir`: The trash directory containing `files` and `info`.
+    /// - `topdir`: The mount point for per-mount trashes, whose info files store paths
+    ///   relative to it. `None` for the home trash, which stores absolute paths.
+    ///
+    struct TrashDir {
+        dir: PathBuf,
+        topdir: Option<PathBuf>,
+    }
+
+    pub fn restore_from_trash(location: &Path, original: &Path) -> io::Result<()> {
+        match fs::rename(location, original) {
+            Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+                fs::copy(location, original)?;
+                fs::remove_file(location).inspect_err(|_| {
+                    let _ = fs::remove_file(original);
+                })?;
+            }
+            result => result?,
+        }
+
+        // `location` is `$trash/files/<name>`, its info file is `$trash/info/<name>.trashinfo`.
+        if let (Some(files_dir), Some(name)) = (location.parent(), location.file_name()) {
+            if let Some(trash_dir) = files_dir.parent() {
+                let mut info_name = name.to_os_string();
+                info_name.push(".trashinfo");
+                let _ = fs::remove_file(trash_dir.join("info").join(info_name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn move_to_trash(path: &Path) -> io::Result<Option<PathBuf>> {
+        let path = absolute_path(path)?;
+        let metadata = fs::symlink_metadata(&path)?;
+        let home = TrashDir {
+            dir: home_trash_dir(),
+            topdir: None,
+        };
+
+        if device_of(&home.dir)? == metadata.dev() {
+            return trash_into(&home, &path, false).map(Some);
+        }
+
+        let topdir = mount_point(&path, metadata.dev());
+        if let Some(trash) = topdir_trash(&topdir) {
+            if let Ok(location) = trash_into(&trash, &path, false) {
+                return Ok(Some(location));
+            }
+        }
+
+        trash_into(&home, &path, true).map(Some)
+    }
+
+    /// Returns `$XDG_DATA_HOME/Trash`, defaulting to `~/.local/share/Trash`.
+    fn home_trash_dir() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("Trash")
+    }
+
+    /// Makes `path` absolute without resolving a symlink in its final component.
+    fn absolute_path(path: &Path) -> io::Result<PathBuf> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+            _ => std::env::current_dir()?,
+        };
+        Ok(parent.join(name))
+    }
+
+    /// Returns the device id of `path`, or of its nearest existing ancestor.
+    fn device_of(path: &Path) -> io::Result<u64> {
+        let mut current = Some(path);
+        while let Some(p) = current {
+            if let Ok(metadata) = fs::metadata(p) {
+                return Ok(metadata.dev());
+            }
+            current = p.parent();
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no existing ancestor for trash directory",
+        ))
+    }
+
+    /// Finds the mount point (`$topdir`) containing `path` on device `dev`.
+    fn mount_point(path: &Path, dev: u64) -> PathBuf {
+        let mut topdir = path.parent().unwrap_or(path);
+        while let Some(parent) = topdir.parent() {
+            match fs::metadata(parent) {
+                Ok(metadata) if metadata.dev() == dev => topdir = parent,
+                _ => break,
+            }
+        }
+        topdir.to_path_buf()
+    }
+
+    /// Resolves the per-mount trash for `topdir`.
+    ///
+    /// Prefers `$topdir/.Trash/$uid` when `$topdir/.Trash` is a real directory with the
+    /// sticky bit set, and otherwise uses (creating it if needed) `$topdir/.Trash-$uid`.
+    fn topdir_trash(topdir: &Path) -> Option<TrashDir> {
+        let uid = unsafe { libc::getuid() };
+
+        let shared = topdir.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared) {
+            if metadata.is_dir() && metadata.permissions().mode() & STICKY_BIT != 0 {
+                let dir = shared.join(uid.to_string());
+                if create_private_dir(&dir).is_ok() {
+                    return Some(TrashDir {
+                        dir,
+                        topdir: Some(topdir.to_path_buf()),
+                    });
+                }
+            }
+        }
+
+        let dir = topdir.join(format!(".Trash-{}", uid));
+        create_private_dir(&dir).ok()?;
+        let metadata = fs::symlink_metadata(&dir).ok()?;
+        (metadata.is_dir() && metadata.uid() == uid).then(|| TrashDir {
+            dir,
+            topdir: Some(topdir.to_path_buf()),
+        })
+    }
+
+    /// Creates a directory readable only by the current user, if it does not exist yet.
+    fn create_private_dir(dir: &Path) -> io::Result<()> {
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)
+    }
+
+    /// Moves `path` into `trash`, writing its `.trashinfo` file first as the spec requires.
+    ///
+    /// When `allow_copy` is set and the trash is on another filesystem, the file is copied
+    /// into the trash and the original is removed afterwards.
+    fn trash_into(trash: &TrashDir, path: &Path, allow_copy: bool) -> io::Result<PathBuf> {
+        let files_dir = trash.dir.join("files");
+        let info_dir = trash.dir.join("info");
+        create_private_dir(&files_dir)?;
+        create_private_dir(&info_dir)?;
+
+        let original_path = match &trash.topdir {
+            Some(topdir) => path.strip_prefix(topdir).unwrap_or(path),
+            None => path,
+        };
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(original_path),
+            Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        let name = path.file_name().unwrap_or_default();
+        let (info_path, mut info_file, trashed_path) = reserve_name(&files_dir, &info_dir, name)?;
+        if let Err(e) = info_file
+            .write_all(info.as_bytes())
+            .and_then(|_| info_file.sync_all())
+        {
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+
+        let moved = match fs::rename(path, &trashed_path) {
+            Err(e) if allow_copy && e.raw_os_error() == Some(libc::EXDEV) => {
+                fs::copy(path, &trashed_path)
+                    .and_then(|_| fs::remove_file(path))
+                    .inspect_err(|_| {
+                        let _ = fs::remove_file(&trashed_path);
+                    })
+            }
+            result => result,
+        };
+
+        match moved {
+            Ok(()) => Ok(trashed_path),
+            Err(e) => {
+                let _ = fs::remove_file(&info_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Picks a name that is free in both `files` and `info`, atomically creating its
+    /// `.trashinfo` file so that concurrent trashers cannot claim the same name.
+    fn reserve_name(
+        files_dir: &Path,
+        info_dir: &Path,
+        name: &std::ffi::OsStr,
+    ) -> io::Result<(PathBuf, File, PathBuf)> {
+        for attempt in 0u32.. {
+            let mut candidate = OsString::from(name);
+            if attempt > 0 {
+                candidate.push(format!(".{}", attempt));
+            }
+
+            let trashed_path = files_dir.join(&candidate);
+            if fs::symlink_metadata(&trashed_path).is_ok() {
+                continue;
+            }
+
+            let mut info_name = candidate;
+            info_name.push(".trashinfo");
+            let info_path = info_dir.join(info_name);
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => return Ok((info_path, file, trashed_path)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!("trash name candidates are unbounded")
+    }
+
+    /// Percent-encodes a path for the `Path=` key of a `.trashinfo` file.
+    fn encode_path(path: &Path) -> String {
+        let mut encoded = String::new();
+        for &byte in path.as_os_str().as_bytes() {
+            if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        encoded
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{
+        ffi::OsString,
+        fs, io,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+        path::{Path, PathBuf},
+        ptr,
+        time::SystemTime,
+    };
+    use winapi::um::shellapi::{
+        SHFileOperationW, FOF_ALLOWUNDO, FOF_NOCONFIRMATION, FOF_NOERRORUI, FOF_SILENT, FO_DELETE,
+        SHFILEOPSTRUCTW,
+    };
+
+    pub fn move_to_trash(path: &Path) -> io::Result<Option<PathBuf>> {
+        let path = std::path::absolute(path)?;
+
+        // `pFrom` is a list of paths terminated by an additional null character.
+        let from: Vec<u16> = path.as_os_str().encode_wide().chain([0, 0]).collect();
+        let mut operation = SHFILEOPSTRUCTW {
+            hwnd: ptr::null_mut(),
+            wFunc: FO_DELETE as u32,
+            pFrom: from.as_ptr(),
+            pTo: ptr::null(),
+            fFlags: FOF_ALLOWUNDO | FOF_NOCONFIRMATION | FOF_NOERRORUI | FOF_SILENT,
+            fAnyOperationsAborted: 0,
+            hNameMappings: ptr::null_mut(),
+            lpszProgressTitle: ptr::null(),
+        };
+
+        let started = SystemTime::now();
+        let result = unsafe { SHFileOperationW(&mut operation) };
+        if result != 0 || operation.fAnyOperationsAborted != 0 {
+            return Err(io::Error::other(format!(
+                "failed to move {} to the Recycle Bin (code {})",
+                path.display(),
+                result
+            )));
+        }
+
+        Ok(find_recycled(&path, started))
+    }
+
+    pub fn restore_from_trash(location: &Path, original: &Path) -> io::Result<()> {
+        fs::rename(location, original)?;
+        if let Some(index_file) = index_file_path(location) {
+            let _ = fs::remove_file(index_file);
+        }
+        Ok(())
+    }
+
+    /// Finds the `$R` file the Recycle Bin stored `path` as.
+    ///
+    /// The Recycle Bin keeps every file as a pair in `<drive>\$Recycle.Bin\<SID>`: `$R...`
+    /// holds the contents and `$I...` records the original path. Only index files written
+    /// since `since` are read.
+    ///
+    fn find_recycled(path: &Path, since: SystemTime) -> Option<PathBuf> {
+        let bin = path.ancestors().last()?.join("$Recycle.Bin");
+
+        for sid_dir in fs::read_dir(bin).ok()?.flatten() {
+            let Ok(entries) = fs::read_dir(sid_dir.path()) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let recent = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|modified| modified >= since);
+                if !recent || !name.to_string_lossy().starts_with("$I") {
+                    continue;
+                }
+
+                let original = fs::read(entry.path())
+                    .ok()
+                    .and_then(|data| parse_index_file(&data));
+                if original.is_some_and(|o| o.eq_ignore_ascii_case(path.as_os_str())) {
+                    let data_name = format!("$R{}", &name.to_string_lossy()[2..]);
+                    return Some(sid_dir.path().join(data_name));
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the `$I` index file belonging to the `$R` file at `location`.
+    fn index_file_path(location: &Path) -> Option<PathBuf> {
+        let name = location.file_name()?.to_string_lossy();
+        let rest = name.strip_prefix("$R")?;
+        Some(location.with_file_name(format!("$I{}", rest)))
+    }
+
+    /// Reads the original path from the contents of a `$I` index file.
+    ///
+    /// Version 1 files store the path as 260 UTF-16 units at offset 24, version 2 files
+    /// store its length at offset 24 followed by the path.
+    ///
+    fn parse_index_file(data: &[u8]) -> Option<OsString> {
+        let version = u64::from_le_bytes(data.get(0..8)?.try_into().ok()?);
+        let path_bytes = match version {
+            1 => data.get(24..24 + 520)?,
+            2 => {
+                let len = u32::from_le_bytes(data.get(24..28)?.try_into().ok()?) as usize;
+                data.get(28..28 + len * 2)?
+            }
+            _ => return None,
+        };
+
+        let wide: Vec<u16> = path_bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&c| c != 0)
+            .collect();
+        Some(OsString::from_wide(&wide))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    use std::{
+        io,
+        path::{Path, PathBuf},
+    };
+
+    pub fn move_to_trash(path: &Path) -> io::Result<Option<PathBuf>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "cannot move {} to the trash on this platform, choose another delete mode",
+                path.display()
+            ),
+        ))
+    }
+
+    pub fn restore_from_trash(_location: &Path, _original: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the trash is not supported on this platform",
+        ))
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{
+    cleanup::CleanupError,
+    config::DeleteMode,
+    journal::{load_runs, record_undo, JournalRun, RunKind},
+    quarantine, trash,
+};
+use std::{io, path::PathBuf};
+
+/// The outcome of undoing a cleanup run.
+///
+/// # Fields
+///
+/// - `run`: The id of the cleanup run that was undone.
+/// - `folder`: The tracked folder of that run.
+/// - `restored`: Files that were moved back to their original path.
+/// - `conflicts`: Files left in the trash or quarantine because a file has since been
+///   created at their original path.
+/// - `failed`: Files that could not be restored, with the reason.
+/// - `not_undoable`: The run had no file left to restore, so nothing was attempted. Its
+///   files were deleted permanently, their location in the trash is unknown, or an
+///   earlier undo restored them already.
+///
+#[derive(Clone, Debug, Default)]
+pub struct UndoReport {
+    pub run: String,
+    pub folder: String,
+    pub restored: Vec<PathBuf>,
+    pub conflicts: Vec<PathBuf>,
+    pub failed: Vec<CleanupError>,
+    pub not_undoable: bool,
+}
+
+impl UndoReport {
+    /// Returns a one-line summary of the report, e.g. `3 restored, 1 conflicts, 0 failed`.
+    pub fn summary(&self) -> String {
+        if self.not_undoable {
+            return "nothing left to restore".to_string();
+        }
+        format!(
+            "{} restored, {} conflicts, {} failed",
+            self.restored.len(),
+            self.conflicts.len(),
+            self.failed.len()
+        )
+    }
+}
+
+/// Returns `true` if `run` is a cleanup that moved files to a known location they can be
+/// restored from, and not all of them have been restored yet.
+pub fn is_undoable(run: &JournalRun) -> bool {
+    run.kind == RunKind::Cleanup && run.restorable().next().is_some()
+}
+
+/// Returns the most recent cleanup run, if any, whether or not it can be undone.
+pub fn last_cleanup_run() -> Option<JournalRun> {
+    load_runs(usize::MAX)
+        .into_iter()
+        .find(|run| run.kind == RunKind::Cleanup)
+}
+
+/// Moves every file removed by a cleanup run back to its original path, and records the
+/// undo in the journal so the restored files are marked as reverted.
+///
+/// Files that were deleted permanently, whose location in the trash is unknown, or that
+/// were already restored by an earlier undo are left out. A file is reported as a conflict and left where it is if another file has since
+/// been created at its original path. Conflicting and failed files stay undoable, so the
+/// undo can be retried.
+///
+/// # Parameters
+///
+/// - `run`: The cleanup run to undo, as loaded from the journal.
+///
+/// # Returns
+///
+/// - The [`UndoReport`] for the run.
+/// - An `Err` if the undo could not be recorded in the journal. The files have already
+///   been restored at that point.
+///
+pub fn undo_run(run: &JournalRun) -> io::Result<UndoReport> {
+    let mut report = UndoReport {
+        run: run.id.clone(),
+        folder: run.folder.clone(),
+        ..UndoReport::default()
+    };
+
+    for entry in run.restorable() {
+        let Some(location) = &entry.location else {
+            continue;
+        };
+        let result = match entry.delete_mode {
+            DeleteMode::Permanent => continue,
+            DeleteMode::Quarantine => quarantine::restore(location).map(|_| ()),
+            DeleteMode::Trash => trash::restore_from_trash(location, &entry.path),
+        };
+
+        match result {
+            Ok(()) => report.restored.push(entry.path.clone()),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                report.conflicts.push(entry.path.clone())
+            }
+            Err(e) => report.failed.push(CleanupError::from_io(&entry.path, &e)),
+        }
+    }
+
+    record_undo(run, &report)?;
+    Ok(report)
+}
+
+/// Undoes the most recent cleanup run.
+///
+/// Older runs are never undone instead, so the files restored are always those of the
+/// cleanup the user saw last.
+///
+/// # Returns
+///
+/// - `Ok(None)` if the journal holds no cleanup.
+/// - `Ok(Some(UndoReport))` once the run was undone and recorded, or with `not_undoable`
+///   set if it has no file left to restore.
+/// - An `Err` as described for [`undo_run`].
+///
+pub fn undo_last_cleanup() -> io::Result<Option<UndoReport>> {
+    let Some(run) = last_cleanup_run() else {
+        return Ok(None);
+    };
+    if !is_undoable(&run) {
+        return Ok(Some(UndoReport {
+            run: run.id,
+            folder: run.folder,
+            not_undoable: true,
+            ..UndoReport::default()
+        }));
+    }
+    undo_run(&run).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::{JournalEntry, Outcome};
+    use chrono::Utc;
+    use std::collections::HashSet;
+
+    /// Returns a cleanup run with one entry for a file removed with `delete_mode`.
+    fn cleanup_run(delete_mode: DeleteMode, location: Option<PathBuf>) -> JournalRun {
+        let entry = JournalEntry {
+            timestamp: Utc::now(),
+            run: "run".to_string(),
+            kind: RunKind::Cleanup,
+            folder: "/data".to_string(),
+            path: PathBuf::from("/data/old.log"),
+            size: Some(3),
+            modified: None,
+            rule: None,
+            delete_mode,
+            outcome: Outcome::Deleted,
+            location,
+            message: None,
+            reverts: None,
+        };
+        JournalRun {
+            id: entry.run.clone(),
+            kind: RunKind::Cleanup,
+            folder: entry.folder.clone(),
+            timestamp: entry.timestamp,
+            entries: vec![entry],
+            restored: HashSet::new(),
+            reverted: false,
+        }
+    }
+
+    #[test]
+    fn trash_entry_without_location_is_not_undoable() {
+        let run = cleanup_run(DeleteMode::Trash, None);
+        assert_eq!(run.restorable().count(), 0);
+        assert!(!is_undoable(&run));
+    }
+
+    #[test]
+    fn trash_entry_with_location_is_undoable() {
+        let location = PathBuf::from("/home/user/.local/share/Trash/files/old.log");
+        let run = cleanup_run(DeleteMode::Trash, Some(location));
+        assert!(is_undoable(&run));
+    }
+}
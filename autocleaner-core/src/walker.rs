@@ -0,0 +1,485 @@
+use crate::{
+    cleanup::{CleanupError, FileFilter},
+    progress::Progress,
+};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+use walkdir::WalkDir;
+
+/// Called for every file collected by a walk, from the thread that found it.
+pub type FileVisitor<'a> = &'a (dyn Fn(&Path, &Metadata) + Sync);
+
+/// The files and errors collected by walking a tracked folder.
+///
+/// # Fields
+///
+/// - `files`: Every regular file passing the folder's patterns, with its metadata, sorted
+///   by path.
+/// - `errors`: Paths that could not be read, sorted by path.
+/// - `cancelled`: Whether the walk was cancelled, leaving `files` incomplete.
+///
+#[derive(Default)]
+pub struct WalkResult {
+    pub files: Vec<(PathBuf, Metadata)>,
+    pub errors: Vec<CleanupError>,
+    pub cancelled: bool,
+}
+
+impl WalkResult {
+    /// Reads the metadata of a file found during the walk, records it and passes it to
+    /// `visitor`.
+    fn add_file(&mut self, path: PathBuf, visitor: FileVisitor) {
+        match fs::metadata(&path) {
+            Ok(metadata) => {
+                visitor(&path, &metadata);
+                self.files.push((path, metadata));
+            }
+            Err(e) => self.errors.push(CleanupError::from_io(&path, &e)),
+        }
+    }
+}
+
+/// Returns the number of worker threads to use for a configured worker count.
+///
+/// # Parameters
+///
+/// - `configured`: The configured worker count, where `0` means one per CPU core.
+///
+pub fn worker_count(configured: usize) -> usize {
+    match configured {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Walks `root` and collects every regular file that passes `filter`.
+///
+/// Directories matched by the filter's exclude patterns are not descended into, and
+/// symbolic links are not followed. With more than one worker the walk is split across
+/// a bounded pool of threads with [`walk_parallel`]; otherwise [`walk_serial`] is used.
+/// Both produce the same result.
+///
+/// Every regular file counts as visited in `progress`, whether or not it passes the
+/// filter. The walk stops early once `progress` is cancelled.
+///
+/// # Parameters
+///
+/// - `root`: The tracked folder to walk.
+/// - `filter`: The folder's compiled include and exclude patterns.
+/// - `workers`: The configured worker count, as accepted by [`worker_count`].
+/// - `progress`: The [`Progress`] receiving the visited file count.
+/// - `visitor`: Called for every collected file as soon as it is found.
+///
+pub fn walk(
+    root: &Path,
+    filter: &FileFilter,
+    workers: usize,
+    progress: &Progress,
+    visitor: FileVisitor,
+) -> WalkResult {
+    match worker_count(workers) {
+        1 => walk_serial(root, filter, progress, visitor),
+        workers => walk_parallel(root, filter, workers, progress, visitor),
+    }
+}
+
+/// Walks `root` on the current thread.
+pub fn walk_serial(
+    root: &Path,
+    filter: &FileFilter,
+    progress: &Progress,
+    visitor: FileVisitor,
+) -> WalkResult {
+    let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+    let mut result = WalkResult::default();
+
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || !e.file_type().is_dir()
+                || !filter.is_excluded_dir(&relative(e.path()))
+        });
+
+    for entry in walker {
+        if progress.is_cancelled() {
+            result.cancelled = true;
+            break;
+        }
+
+        match entry {
+            Ok(entry) if entry.file_type().is_file() => {
+                progress.add_visited();
+                if filter.is_included_file(&relative(entry.path())) {
+                    result.add_file(entry.into_path(), visitor);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => result.errors.push(CleanupError::from_walk(root, &e)),
+        }
+    }
+
+    // Sorted like the errors of a parallel walk, so both report the same tree alike.
+    result.errors.sort_by(|a, b| a.path.cmp(&b.path));
+    result
+}
+
+/// Walks `root` with a pool of `workers` threads sharing a queue of directories.
+///
+/// Each worker lists one directory at a time, queues its subdirectories and collects its
+/// files. The listings are assembled in path order afterwards, so they match
+/// [`walk_serial`] regardless of the order in which directories were visited. A `root`
+/// that is not a directory is handed to [`walk_serial`].
+///
+pub fn walk_parallel(
+    root: &Path,
+    filter: &FileFilter,
+    workers: usize,
+    progress: &Progress,
+    visitor: FileVisitor,
+) -> WalkResult {
+    if !fs::metadata(root).is_ok_and(|m| m.is_dir()) {
+        return walk_serial(root, filter, progress, visitor);
+    }
+
+    let (sender, receiver) = unbounded();
+    // Directories queued or being listed. The walk is done when this drops to zero.
+    let pending = AtomicUsize::new(1);
+    sender
+        .send(Some(root.to_path_buf()))
+        .expect("receiver is alive");
+
+    let listings = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|_| {
+                let worker = Worker {
+                    root,
+                    filter,
+                    workers,
+                    progress,
+                    visitor,
+                    sender: sender.clone(),
+                    receiver: receiver.clone(),
+                    pending: &pending,
+                };
+                scope.spawn(move || worker.run())
+            })
+            .collect();
+
+        let mut listings = Listings::default();
+        for handle in handles {
+            let partial = handle.join().expect("walk worker panicked");
+            listings.dirs.extend(partial.dirs);
+            listings.errors.extend(partial.errors);
+        }
+        listings
+    });
+
+    let mut result = listings.assemble(root);
+    result.cancelled = progress.is_cancelled();
+    result
+}
+
+/// An entry of a listed directory, kept until the walk is assembled in path order.
+enum Listed {
+    File(PathBuf, Metadata),
+    Dir(PathBuf),
+}
+
+impl Listed {
+    fn path(&self) -> &Path {
+        match self {
+            Listed::File(path, _) | Listed::Dir(path) => path,
+        }
+    }
+}
+
+/// The directories listed by the workers of a [`walk_parallel`] pool.
+///
+/// # Fields
+///
+/// - `dirs`: The collected files and subdirectories of every listed directory, sorted by
+///   file name.
+/// - `errors`: Paths that could not be read.
+///
+#[derive(Default)]
+struct Listings {
+    dirs: HashMap<PathBuf, Vec<Listed>>,
+    errors: Vec<CleanupError>,
+}
+
+impl Listings {
+    /// Flattens the listings depth first from `root`, which yields the files in the same
+    /// order as a serial walk sorted by file name.
+    fn assemble(mut self, root: &Path) -> WalkResult {
+        let mut result = WalkResult::default();
+        let mut stack: Vec<_> = self
+            .dirs
+            .remove(root)
+            .map(Vec::into_iter)
+            .into_iter()
+            .collect();
+
+        while let Some(entries) = stack.last_mut() {
+            match entries.next() {
+                Some(Listed::File(path, metadata)) => result.files.push((path, metadata)),
+                Some(Listed::Dir(path)) => {
+                    if let Some(children) = self.dirs.remove(&path) {
+                        stack.push(children.into_iter());
+                    }
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+
+        result.errors = self.errors;
+        result.errors.sort_by(|a, b| a.path.cmp(&b.path));
+        result
+    }
+}
+
+/// One thread of a [`walk_parallel`] pool.
+///
+/// # Fields
+///
+/// - `sender`, `receiver`: The shared queue of directories still to be listed. `None`
+///   tells a worker to stop.
+/// - `pending`: The number of directories queued or being listed. The walk is done when
+///   this drops to zero.
+///
+struct Worker<'a> {
+    root: &'a Path,
+    filter: &'a FileFilter,
+    workers: usize,
+    progress: &'a Progress,
+    visitor: FileVisitor<'a>,
+    sender: Sender<Option<PathBuf>>,
+    receiver: Receiver<Option<PathBuf>>,
+    pending: &'a AtomicUsize,
+}
+
+impl Worker<'_> {
+    /// Lists directories from the queue until the walk is done.
+    ///
+    /// The worker that finishes the last pending directory sends a `None` to every
+    /// worker. Once the walk is cancelled, queued directories are drained without being
+    /// listed.
+    ///
+    fn run(self) -> Listings {
+        let mut listings = Listings::default();
+
+        while let Ok(Some(dir)) = self.receiver.recv() {
+            if !self.progress.is_cancelled() {
+                let listed = self.list_dir(&dir, &mut listings.errors);
+                listings.dirs.insert(dir, listed);
+            }
+
+            if self.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+                for _ in 0..self.workers.max(1) {
+                    let _ = self.sender.send(None);
+                }
+            }
+        }
+
+        listings
+    }
+
+    /// Collects the files of `dir` and queues its subdirectories.
+    ///
+    /// # Returns
+    ///
+    /// The collected files and subdirectories, sorted by file name.
+    ///
+    fn list_dir(&self, dir: &Path, errors: &mut Vec<CleanupError>) -> Vec<Listed> {
+        let relative = |path: &Path| path.strip_prefix(self.root).unwrap_or(path).to_path_buf();
+        let mut listed = Vec::new();
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push(CleanupError::from_io(dir, &e));
+                return listed;
+            }
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    errors.push(CleanupError::from_io(dir, &e));
+                    continue;
+                }
+            };
+
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(e) => {
+                    errors.push(CleanupError::from_io(&path, &e));
+                    continue;
+                }
+            };
+
+            if file_type.is_dir() {
+                if !self.filter.is_excluded_dir(&relative(&path)) {
+                    self.pending.fetch_add(1, Ordering::SeqCst);
+                    let _ = self.sender.send(Some(path.clone()));
+                    listed.push(Listed::Dir(path));
+                }
+            } else if file_type.is_file() {
+                self.progress.add_visited();
+                if !self.filter.is_included_file(&relative(&path)) {
+                    continue;
+                }
+                match fs::metadata(&path) {
+                    Ok(metadata) => {
+                        (self.visitor)(&path, &metadata);
+                        listed.push(Listed::File(path, metadata));
+                    }
+                    Err(e) => errors.push(CleanupError::from_io(&path, &e)),
+                }
+            }
+        }
+
+        listed.sort_by(|a, b| a.path().file_name().cmp(&b.path().file_name()));
+        listed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Creates a synthetic tree of `depth` levels with `fanout` subdirectories and
+    /// `files` files per directory, below a fresh directory in the system temp dir.
+    fn synthetic_tree(name: &str, depth: u32, fanout: u32, files: u32) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("autocleaner-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let mut dirs = vec![(root.clone(), 0)];
+        while let Some((dir, level)) = dirs.pop() {
+            fs::create_dir_all(&dir).unwrap();
+            for i in 0..files {
+                let extension = if i % 3 == 0 { "log" } else { "txt" };
+                fs::write(
+                    dir.join(format!("file{}.{}", i, extension)),
+                    vec![0; i as usize],
+                )
+                .unwrap();
+            }
+            if level < depth {
+                for i in 0..fanout {
+                    let name = if i == 0 {
+                        "cache".to_string()
+                    } else {
+                        format!("dir{}", i)
+                    };
+                    dirs.push((dir.join(name), level + 1));
+                }
+            }
+        }
+        root
+    }
+
+    fn paths(result: &WalkResult) -> Vec<PathBuf> {
+        result.files.iter().map(|(path, _)| path.clone()).collect()
+    }
+
+    fn error_paths(result: &WalkResult) -> Vec<PathBuf> {
+        result.errors.iter().map(|e| e.path.clone()).collect()
+    }
+
+    fn serial(root: &Path, filter: &FileFilter) -> WalkResult {
+        walk_serial(root, filter, &Progress::default(), &|_, _| {})
+    }
+
+    fn parallel(root: &Path, filter: &FileFilter, workers: usize) -> WalkResult {
+        walk_parallel(root, filter, workers, &Progress::default(), &|_, _| {})
+    }
+
+    #[test]
+    fn parallel_walk_matches_serial_walk() {
+        let root = synthetic_tree("walk-match", 3, 4, 6);
+        let filters = [
+            FileFilter::new(&[], &[]).unwrap(),
+            FileFilter::new(&["**/*.log".to_string()], &["**/cache/**".to_string()]).unwrap(),
+        ];
+
+        for filter in &filters {
+            let serial = serial(&root, filter);
+            assert!(!serial.files.is_empty());
+            for workers in [1, 2, 8] {
+                let parallel = parallel(&root, filter, workers);
+                assert_eq!(paths(&parallel), paths(&serial));
+                assert_eq!(error_paths(&parallel), error_paths(&serial));
+            }
+        }
+
+        let missing = root.join("missing");
+        let filter = &filters[0];
+        let serial_errors = error_paths(&serial(&missing, filter));
+        assert_eq!(serial_errors, vec![missing.clone()]);
+        assert_eq!(error_paths(&parallel(&missing, filter, 4)), serial_errors);
+
+        let progress = Progress::default();
+        walk_parallel(&root, filter, 4, &progress, &|_, _| {});
+        assert_eq!(
+            progress.snapshot().visited,
+            serial(&root, filter).files.len()
+        );
+
+        progress.cancel();
+        for result in [
+            walk_serial(&root, filter, &progress, &|_, _| {}),
+            walk_parallel(&root, filter, 4, &progress, &|_, _| {}),
+        ] {
+            assert!(result.cancelled);
+            assert!(result.files.is_empty());
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Compares the serial and parallel walks on a larger tree.
+    ///
+    /// Run with `cargo test --release -- --ignored --nocapture walk_benchmark`.
+    ///
+    #[test]
+    #[ignore]
+    fn walk_benchmark() {
+        let root = synthetic_tree("walk-bench", 4, 6, 40);
+        let filter = FileFilter::new(&[], &[]).unwrap();
+
+        let start = Instant::now();
+        let serial = serial(&root, &filter);
+        let serial_time = start.elapsed();
+        println!("serial: {} files in {:?}", serial.files.len(), serial_time);
+
+        for workers in [2, 4, 8, worker_count(0)] {
+            let start = Instant::now();
+            let parallel = parallel(&root, &filter, workers);
+            let parallel_time = start.elapsed();
+            assert_eq!(paths(&parallel), paths(&serial));
+            println!(
+                "parallel ({} workers): {} files in {:?} ({:.2}x)",
+                workers,
+                parallel.files.len(),
+                parallel_time,
+                serial_time.as_secs_f64() / parallel_time.as_secs_f64()
+            );
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,5 +1,12 @@
 /// Build script entry point for embedding a Windows application icon.
+///
+/// The icon is only embedded when building for Windows.
+///
 fn main() {
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows") {
+        return;
+    }
+
     let mut res = winres::WindowsResource::new();
     res.set_icon("../resources/icon.ico");
     res.compile().unwrap();
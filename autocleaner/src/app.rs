@@ -1,26 +1,74 @@
 use crate::{
-    cleanup::{remove_old_files, scan_folder},
-    config::{save_config, FolderConfig, TrackedFolder},
-    fs, get_config_path,
-    startup::{get_startup_shortcut_path, is_startup_enabled, set_startup},
+    autosave::{AutoSave, SAVE_DELAY},
+    config_watcher::ConfigWatcher,
+    preview::{PlanPreview, PreviewAction},
+    startup::{is_startup_enabled, repair_startup, set_startup},
+    task::{BackgroundTask, TaskOutput},
+};
+use autocleaner_core::{
+    cleanup::{CleanupError, CleanupPlan, CleanupReport, FileFilter},
+    config::{
+        merge_configs, save_config, DeleteMode, FolderConfig, Retention, RuleCombination,
+        ScheduledAction, TimestampBasis, TrackedFolder,
+    },
+    journal::{load_runs, JournalRun},
+    quarantine::QuarantineManifest,
+    schedule::Schedule,
+    undo::UndoReport,
+};
+use chrono::{NaiveTime, Timelike};
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
 };
 
 /// The main application struct for AutoCleaner.
 ///
 /// `AutoCleanerApp` holds the state and configuration for the application, including:
-/// - The folder configuration used to determine which directories to monitor or clean.
+/// - The folder configuration used to determine which directories to monitor or clean,
+///   why it could not be loaded, if it could not, and which of its changes are unsaved.
 /// - A log string that captures recent activity or output.
+/// - The most recent cleanup plan of each scanned folder.
+/// - The scans, cleanups, purges, restores and undos currently running in the background.
+/// - The manifest of files currently held in quarantine.
+/// - The runs read back from the journal for the history view.
+/// - The text of any open include/exclude pattern editors.
 /// - A flag indicating whether the app should automatically run at startup.
 ///
 /// # Fields
 ///
 /// - `config`: The [`FolderConfig`] used to store information about which folders should be cleaned and how.
+/// - `config_error`: Why the config file could not be loaded. While set, `config` starts
+///   out empty and is not saved, so the file on disk is kept for repair.
+/// - `autosave`: The [`AutoSave`] deciding when changes to `config` are written.
+/// - `watcher`: The [`ConfigWatcher`] noticing changes other processes make to the config
+///   file, or `None` if it cannot be watched.
+/// - `save_error`: Why the config could last not be saved, so a failure that repeats on
+///   every save is logged only once.
 /// - `log`: A string buffer containing the latest log output, usually updated after operations.
+/// - `plans`: The last [`CleanupPlan`] built by "Scan" for each folder path, shown as a
+///   [`PlanPreview`] whose checked files are removed by "Remove".
+/// - `tasks`: The [`BackgroundTask`] running for each folder path, and the purge, restore
+///   or undo running under [`QUARANTINE_TASK`], if any.
+/// - `quarantine`: The [`QuarantineManifest`] shown in the quarantine section, reloaded after changes.
+/// - `history`: The most recent [`JournalRun`]s, read when the history view is first opened.
+/// - `pattern_editors`: The pattern text being edited, keyed by folder path. A folder has an
+///   entry only while its editor is open.
 /// - `run_at_startup`: Whether the app should be scheduled to run automatically on system startup.
 ///
 pub struct AutoCleanerApp {
     pub config: FolderConfig,
+    pub config_error: Option<String>,
+    pub autosave: AutoSave<FolderConfig>,
+    pub watcher: Option<ConfigWatcher>,
+    pub save_error: Option<String>,
     pub log: String,
+    pub plans: HashMap<String, PlanPreview>,
+    pub tasks: HashMap<String, BackgroundTask>,
+    pub quarantine: QuarantineManifest,
+    pub history: Option<Vec<JournalRun>>,
+    pub pattern_editors: HashMap<String, String>,
     pub run_at_startup: bool,
 }
 
@@ -28,30 +76,70 @@ impl Default for AutoCleanerApp {
     /// Creates a default instance of [`AutoCleanerApp`].
     ///
     /// This implementation attempts to load the saved folder configuration from disk.
-    /// If the config file exists and can be read and parsed, it is used.
-    /// Otherwise, a default [`FolderConfig`] is used instead.
+    /// If the config file does not exist, a default [`FolderConfig`] is used instead.
+    /// If it cannot be loaded, the default is used as well and the error is kept in
+    /// `config_error`. A damaged file that was replaced by a backup is noted in `log`.
     ///
     /// The `run_at_startup` flag is initialized based on the presence of a startup shortcut.
-    /// The `log` field is initialized as an empty string.
+    /// A startup entry pointing at a missing or outdated executable is repaired first, which
+    /// is noted in the otherwise empty `log`.
     ///
     /// # Behavior
-    /// - Reads the config from the path returned by [`get_config_path()`].
-    /// - Parses the config using `serde_json`. If parsing fails, it falls back to `FolderConfig::default()`.
-    /// - Checks for the existence of the startup shortcut via [`get_startup_shortcut_path()`].
+    /// - Loads and, if needed, migrates or recovers the config via
+    ///   [`FolderConfig::load_or_recover()`].
+    /// - Starts watching the config file for external changes via [`ConfigWatcher::new()`].
+    /// - Repairs the startup entry via [`repair_startup()`].
+    /// - Checks whether the app runs at startup via [`is_startup_enabled()`].
     ///
     fn default() -> Self {
-        let config_path = get_config_path();
-        let config = if config_path.exists() {
-            let content = fs::read_to_string(&config_path).unwrap_or_default();
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            FolderConfig::default()
+        let mut log = String::new();
+        let (config, config_error) = match FolderConfig::load_or_recover() {
+            Ok((config, recovery)) => {
+                if let Some(recovery) = recovery {
+                    log.push_str(&format!("♻ Recovered the config: {}\n", recovery));
+                }
+                (config, None)
+            }
+            Err(e) => (FolderConfig::default(), Some(e.to_string())),
+        };
+
+        let watcher = match ConfigWatcher::new() {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                log.push_str(&format!("⚠ Not watching the config for changes: {}\n", e));
+                None
+            }
         };
 
+        match repair_startup() {
+            Ok(Some(target)) => {
+                log.push_str(&format!("🔧 Startup entry now runs {}\n", target.display()))
+            }
+            Ok(None) => {}
+            Err(e) => log.push_str(&format!("⚠ Failed to repair the startup entry: {}\n", e)),
+        }
+
+        let quarantine = QuarantineManifest::load().unwrap_or_else(|e| {
+            log.push_str(&format!(
+                "⚠ Failed to load the quarantine manifest: {}\n",
+                e
+            ));
+            QuarantineManifest::default()
+        });
+
         Self {
-            run_at_startup: get_startup_shortcut_path().exists(),
+            run_at_startup: is_startup_enabled(),
+            autosave: AutoSave::new(config.clone(), SAVE_DELAY),
+            watcher,
             config,
-            log: String::new(),
+            config_error,
+            save_error: None,
+            log,
+            plans: HashMap::new(),
+            tasks: HashMap::new(),
+            quarantine,
+            history: None,
+            pattern_editors: HashMap::new(),
         }
     }
 }
@@ -61,40 +149,72 @@ impl eframe::App for AutoCleanerApp {
     ///
     /// This method is called by `eframe` every frame to redraw the GUI and handle user interactions.
     /// It includes:
-    /// - Handling window events.
+    /// - Handling window events, saving unsaved config changes when the window closes.
+    /// - Merging changes other processes made to the config file, see [`merge_configs`].
     /// - Displaying and updating tracked folders.
+    /// - Saving the config once it changed and the change settled, see [`AutoSave`].
     /// - Adding new folders to track using a folder picker.
-    /// - Running scans and cleanup operations based on file age.
-    /// - Managing Windows startup behavior.
+    /// - Building cleanup plans and executing them in the background.
+    /// - Managing startup behavior.
     /// - Displaying a scrollable log of actions and events.
     ///
     /// # UI Breakdown
     ///
     /// - **Add Folder**: Opens a folder picker and adds the selected folder to tracking, if not already tracked.
     /// - **Tracked Folders**: Shows a list of currently tracked folders with:
-    ///   - A slider to set how many days old files must be to qualify for scanning/deletion.
-    ///   - Buttons to scan, delete, or untrack each folder.
-    /// - **Startup Toggle**: Lets the user choose whether the app should run at Windows startup.
+    ///   - A slider to set how many days old files must be to qualify for scanning/deletion,
+    ///     which can be switched off for folders that are only bounded by size.
+    ///   - A selector for which file timestamp (modified, accessed, created or changed) the age is measured by.
+    ///   - Optional rules for files larger than a size limit, for a folder quota and for
+    ///     keeping only the newest files, and whether files must match any or all rules.
+    ///   - An option to prune directories left empty by a cleanup.
+    ///   - A selector for whether removed files go to the trash, to quarantine, or are deleted permanently.
+    ///   - A pattern editor for include globs and `!`-prefixed exclude globs, one per line.
+    ///   - Buttons to scan, delete, or untrack each folder. "Scan" builds a dry-run plan and
+    ///     "Remove" executes the previewed plan, so only previewed files are deleted. Both run
+    ///     in the background; while they do, the row shows their progress and a button to
+    ///     cancel them.
+    ///   - A preview table of the planned files, sortable and filterable, where files can be
+    ///     unchecked to spare them or excluded from future cleanups altogether.
+    /// - **Quarantine**: Lists quarantined files with a button to restore each one, the grace
+    ///   period after which they are purged, and a button to purge expired files now.
+    /// - **History**: Lists past scans and cleanups recorded in the journal.
+    /// - **Startup Toggle**: Lets the user choose whether the app should run at startup, with
+    ///   a Startup folder shortcut on Windows and an XDG autostart entry or systemd user
+    ///   timer on Linux.
     /// - **Log Viewer**: A scrollable area where recent events (like added folders or file deletions) are displayed.
     ///
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
         use eframe::egui::{CentralPanel, ScrollArea};
 
         for event in ctx.input(|i| i.viewport().events.clone()) {
-            if matches!(event, eframe::egui::ViewportEvent::Close) {}
+            if matches!(event, eframe::egui::ViewportEvent::Close) {
+                self.flush_config();
+            }
+        }
+
+        if let Some(watcher) = &mut self.watcher {
+            if watcher.poll() {
+                self.reload_external_config();
+            }
+            ctx.request_repaint_after(CONFIG_CHECK_INTERVAL);
+        }
+
+        self.poll_tasks();
+        if !self.tasks.is_empty() {
+            ctx.request_repaint_after(Duration::from_millis(100));
         }
 
         CentralPanel::default().show(ctx, |ui| {
             ui.heading("AutoCleaner");
+            self.show_config_error(ui);
             if ui.button("➕ Add Folder to Track").clicked() {
                 if let Some(path) = rfd::FileDialog::new().pick_folder() {
                     let path_str = path.to_string_lossy().to_string();
                     if !self.config.folders.iter().any(|f| f.path == path_str) {
-                        self.config.folders.push(TrackedFolder {
-                            path: path_str.clone(),
-                            days: 200,
-                        });
-                        save_config(&self.config);
+                        self.config
+                            .folders
+                            .push(TrackedFolder::new(path_str.clone(), 200));
                         self.log.push_str(&format!("✔ Added: {}\n", path_str));
                     } else {
                         self.log
@@ -104,62 +224,214 @@ impl eframe::App for AutoCleanerApp {
             }
 
             ui.separator();
-            ui.label("📁 Tracked folders:");
+            ui.horizontal(|ui| {
+                ui.label("📁 Tracked folders:");
+                ui.add_space(16.0);
+                ui.label("🧵 Scan threads:");
+                ui.add(
+                    eframe::egui::DragValue::new(&mut self.config.scan_workers).clamp_range(0..=64),
+                )
+                .on_hover_text("0 uses one thread per CPU core");
+            });
 
+            let workers = self.config.scan_workers;
             let mut to_remove = None;
             for (index, folder) in self.config.folders.iter_mut().enumerate() {
+                let before = folder.clone();
                 ui.horizontal(|ui| {
                     ui.label("📂");
                     ui.label(&folder.path);
 
-                    ui.label("Days:");
-                    ui.add(eframe::egui::Slider::new(&mut folder.days, 1..=365));
+                    ui.checkbox(&mut folder.age_enabled, "Days:");
+                    ui.add_enabled(
+                        folder.age_enabled,
+                        eframe::egui::Slider::new(&mut folder.days, 1..=365),
+                    );
 
-                    if ui.button("🔍 Scan").clicked() {
-                        let count = scan_folder(&folder.path, folder.days);
-                        self.log.push_str(&format!(
-                            "🔍 {} files older than {} days in {}\n",
-                            count, folder.days, folder.path
-                        ));
+                    eframe::egui::ComboBox::from_id_source(("timestamp", index))
+                        .selected_text(folder.timestamp.to_string())
+                        .show_ui(ui, |ui| {
+                            for basis in [
+                                TimestampBasis::Modified,
+                                TimestampBasis::Accessed,
+                                TimestampBasis::Created,
+                                TimestampBasis::Changed,
+                            ] {
+                                ui.selectable_value(
+                                    &mut folder.timestamp,
+                                    basis,
+                                    basis.to_string(),
+                                );
+                            }
+                        });
+
+                    eframe::egui::ComboBox::from_id_source(("delete_mode", index))
+                        .selected_text(folder.delete_mode.to_string())
+                        .show_ui(ui, |ui| {
+                            for mode in [
+                                DeleteMode::Trash,
+                                DeleteMode::Quarantine,
+                                DeleteMode::Permanent,
+                            ] {
+                                ui.selectable_value(
+                                    &mut folder.delete_mode,
+                                    mode,
+                                    mode.to_string(),
+                                );
+                            }
+                        });
+
+                    if let Some(task) = self.tasks.get(&folder.path) {
+                        ui.spinner();
+                        ui.label(task.status());
+                        if ui.button("⏹ Cancel").clicked() {
+                            task.cancel();
+                        }
+                    } else {
+                        if ui.button("🔍 Scan").clicked() {
+                            let task = BackgroundTask::scan(folder.clone(), workers, ctx);
+                            self.tasks.insert(folder.path.clone(), task);
+                        }
+
+                        let checked = self
+                            .plans
+                            .get(&folder.path)
+                            .map_or(0, PlanPreview::checked_count);
+                        if ui
+                            .add_enabled(checked > 0, eframe::egui::Button::new("🗑 Remove"))
+                            .on_disabled_hover_text("Scan the folder and check files to remove")
+                            .clicked()
+                        {
+                            if let Some(preview) = self.plans.remove(&folder.path) {
+                                let spared = preview.plan.len() - checked;
+                                if spared > 0 {
+                                    self.log.push_str(&format!(
+                                        "⏭ Sparing {} unchecked files in {}\n",
+                                        spared, folder.path
+                                    ));
+                                }
+                                let plan = preview.checked_plan();
+                                let task = BackgroundTask::cleanup(folder.clone(), plan, ctx);
+                                self.tasks.insert(folder.path.clone(), task);
+                            }
+                        }
                     }
 
-                    if ui.button("🗑 Remove").clicked() {
-                        let deleted = remove_old_files(&folder.path, folder.days);
-                        self.log.push_str(&format!(
-                            "🗑 Removed {} files older than {} days from {}\n",
-                            deleted, folder.days, folder.path
-                        ));
+                    if ui.button("🧩 Patterns").clicked()
+                        && self.pattern_editors.remove(&folder.path).is_none()
+                    {
+                        self.pattern_editors
+                            .insert(folder.path.clone(), patterns_to_text(folder));
                     }
 
                     if ui.button("❌ Untrack").clicked() {
                         to_remove = Some(index);
                     }
                 });
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.add_space(24.0);
+                    show_rules(ui, index, folder);
+                });
+
+                if *folder != before {
+                    self.plans.remove(&folder.path);
+                }
+
+                // Drawn after the check above, as the schedule does not affect the plan.
+                ui.horizontal_wrapped(|ui| {
+                    ui.add_space(24.0);
+                    show_schedule(ui, index, folder);
+                });
+
+                if let Some(preview) = self.plans.get_mut(&folder.path) {
+                    let mut action = None;
+                    eframe::egui::CollapsingHeader::new(format!(
+                        "📋 Preview: {} of {} files checked",
+                        preview.checked_count(),
+                        preview.plan.len()
+                    ))
+                    .id_source(("preview", &folder.path))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        action = preview.show(ui, &folder.path);
+                    });
+
+                    if let Some(PreviewAction::Exclude(file)) = action {
+                        let pattern = preview.exclude(file);
+                        if let Some(text) = self.pattern_editors.get_mut(&folder.path) {
+                            text.push_str(&format!("\n!{}", pattern));
+                        }
+                        self.log.push_str(&format!(
+                            "🚫 Always keeping {} in {}\n",
+                            pattern, folder.path
+                        ));
+                        folder.exclude.push(pattern);
+                    }
+                }
+
+                if let Some(text) = self.pattern_editors.get_mut(&folder.path) {
+                    let mut apply = false;
+                    ui.horizontal(|ui| {
+                        ui.add_space(24.0);
+                        ui.add(
+                            eframe::egui::TextEdit::multiline(text)
+                                .desired_rows(3)
+                                .hint_text("**/*.log\n!**/.git/**"),
+                        );
+                        apply = ui.button("✔ Apply").clicked();
+                    });
+
+                    if apply {
+                        let (include, exclude) = patterns_from_text(text);
+                        match FileFilter::new(&include, &exclude) {
+                            Ok(_) => {
+                                folder.include = include;
+                                folder.exclude = exclude;
+                                self.pattern_editors.remove(&folder.path);
+                                self.plans.remove(&folder.path);
+                                self.log.push_str(&format!(
+                                    "🧩 Updated patterns for {}\n",
+                                    folder.path
+                                ));
+                            }
+                            Err(e) => {
+                                self.log.push_str(&format!("⚠ Invalid pattern: {}\n", e));
+                            }
+                        }
+                    }
+                }
             }
 
             if let Some(index) = to_remove {
                 let removed = self.config.folders.remove(index);
-                save_config(&self.config);
+                self.plans.remove(&removed.path);
+                if let Some(task) = self.tasks.get(&removed.path) {
+                    task.cancel();
+                }
                 self.log.push_str(&format!(
                     "❌ Folder removed from tracking: {}\n",
                     removed.path
                 ));
             }
 
-            save_config(&self.config);
-
             ui.separator();
-            self.run_at_startup = is_startup_enabled();
+            self.show_quarantine(ui);
+            self.show_history(ui);
 
+            ui.separator();
             if ui
-                .checkbox(&mut self.run_at_startup, "Run at Windows startup")
+                .checkbox(&mut self.run_at_startup, "Run at startup")
                 .clicked()
             {
-                set_startup(self.run_at_startup);
-                self.log.push_str(&format!(
-                    "🔁 Startup {}abled\n",
-                    if self.run_at_startup { "en" } else { "dis" }
-                ));
+                let action = if self.run_at_startup { "en" } else { "dis" };
+                match set_startup(self.run_at_startup) {
+                    Ok(()) => self.log.push_str(&format!("🔁 Startup {}abled\n", action)),
+                    Err(e) => self
+                        .log
+                        .push_str(&format!("⚠ Failed to {}able startup: {}\n", action, e)),
+                }
+                self.run_at_startup = is_startup_enabled();
             }
 
             ui.separator();
@@ -174,5 +446,752 @@ impl eframe::App for AutoCleanerApp {
                 ui.monospace(&self.log);
             });
         });
+
+        self.autosave_config(ctx);
+    }
+}
+
+/// How often the config file is checked for external changes while the window is idle.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of most recent runs shown in the history view.
+const MAX_HISTORY_RUNS: usize = 50;
+
+/// The key in [`AutoCleanerApp::tasks`] of a running purge, restore or undo. Folder paths are
+/// absolute, so it never names a tracked folder.
+const QUARANTINE_TASK: &str = "quarantine";
+
+/// The maximum number of individual errors or skipped files logged for one operation.
+const MAX_LOGGED_ENTRIES: usize = 20;
+
+/// Appends the result of a scan to the log.
+///
+/// Logs the number and size of planned files, any timestamp fallbacks, and every path
+/// that could not be scanned.
+///
+fn log_plan(log: &mut String, plan: &CleanupPlan) {
+    log.push_str(&format!(
+        "🔍 {} files ({} bytes) to clean in {}\n",
+        plan.len(),
+        plan.total_size(),
+        plan.root
+    ));
+    if plan.timestamp_fallbacks > 0 {
+        log.push_str(&format!(
+            "⚠ {} files have no {} time, used their modified time instead\n",
+            plan.timestamp_fallbacks,
+            plan.timestamp_basis.to_string().to_lowercase()
+        ));
+    }
+    log_errors(log, &plan.errors);
+}
+
+/// Appends the result of executing a plan to the log.
+///
+/// Logs how many files were removed, skipped and failed, the pruned directories, and
+/// the individual skipped and failed files.
+///
+fn log_report(log: &mut String, plan: &CleanupPlan, report: &CleanupReport) {
+    log.push_str(&format!(
+        "🗑 {} of {} planned files ({} bytes removed) in {} ({})\n",
+        report.summary(),
+        plan.len(),
+        report.deleted_size(),
+        plan.root,
+        plan.delete_mode
+    ));
+    if report.pruned_dirs > 0 {
+        log.push_str(&format!(
+            "🗑 Pruned {} empty directories from {}\n",
+            report.pruned_dirs, plan.root
+        ));
+    }
+    for skipped in report.skipped.iter().take(MAX_LOGGED_ENTRIES) {
+        log.push_str(&format!(
+            "⏭ Skipped {}: {}\n",
+            skipped.file.path.display(),
+            skipped.reason
+        ));
+    }
+    if report.skipped.len() > MAX_LOGGED_ENTRIES {
+        log.push_str(&format!(
+            "⏭ … and {} more skipped\n",
+            report.skipped.len() - MAX_LOGGED_ENTRIES
+        ));
+    }
+    log_errors(log, &report.failed);
+}
+
+/// Appends errors to the log, listing at most [`MAX_LOGGED_ENTRIES`] of them.
+fn log_errors(log: &mut String, errors: &[CleanupError]) {
+    for error in errors.iter().take(MAX_LOGGED_ENTRIES) {
+        log.push_str(&format!("⚠ {}\n", error));
+    }
+    if errors.len() > MAX_LOGGED_ENTRIES {
+        log.push_str(&format!(
+            "⚠ … and {} more errors\n",
+            errors.len() - MAX_LOGGED_ENTRIES
+        ));
+    }
+}
+
+/// The number of bytes in a megabyte, used by the file size rule editor.
+const MEGABYTE: u64 = 1024 * 1024;
+
+/// The number of bytes in a gigabyte, used by the folder quota editor.
+const GIGABYTE: u64 = 1024 * MEGABYTE;
+
+/// Draws the editors for a folder's size, quota and retention rules, their combination,
+/// and empty directory pruning.
+///
+/// Each rule has a checkbox that enables it. Sizes are edited in megabytes or gigabytes
+/// and only written back when their widgets change, so limits that are not whole
+/// megabytes or gigabytes are preserved until edited. Retention groups are entered as
+/// whitespace-separated file name patterns.
+///
+fn show_rules(ui: &mut eframe::egui::Ui, index: usize, folder: &mut TrackedFolder) {
+    use eframe::egui::{ComboBox, DragValue, TextEdit};
+
+    let mut size_enabled = folder.max_file_size.is_some();
+    let mut size_mb = folder.max_file_size.map_or(100, |bytes| bytes / MEGABYTE);
+    let toggled = ui
+        .checkbox(&mut size_enabled, "Files larger than")
+        .changed();
+    let edited = ui
+        .add_enabled(
+            size_enabled,
+            DragValue::new(&mut size_mb)
+                .clamp_range(1..=u64::MAX / MEGABYTE)
+                .suffix(" MB"),
+        )
+        .changed();
+    if toggled || edited {
+        folder.max_file_size = size_enabled.then_some(size_mb * MEGABYTE);
+    }
+
+    let mut quota_enabled = folder.quota.is_some();
+    let mut quota_gb = folder.quota.map_or(10, |bytes| bytes / GIGABYTE);
+    let toggled = ui.checkbox(&mut quota_enabled, "Folder quota").changed();
+    let edited = ui
+        .add_enabled(
+            quota_enabled,
+            DragValue::new(&mut quota_gb)
+                .clamp_range(1..=u64::MAX / GIGABYTE)
+                .suffix(" GB"),
+        )
+        .changed();
+    if toggled || edited {
+        folder.quota = quota_enabled.then_some(quota_gb * GIGABYTE);
+    }
+
+    let mut keep_enabled = folder.keep_newest.is_some();
+    if ui.checkbox(&mut keep_enabled, "Keep newest").changed() {
+        folder.keep_newest = keep_enabled.then(|| Retention {
+            keep: 5,
+            group_by: Vec::new(),
+        });
+    }
+    if let Some(retention) = &mut folder.keep_newest {
+        ui.add(DragValue::new(&mut retention.keep).clamp_range(0..=10_000));
+        ui.label("grouped by");
+
+        // Keep the raw text while editing so separators can be typed.
+        let id = ui.id().with(("group_by", index));
+        let mut text = ui
+            .data_mut(|d| d.get_temp::<String>(id))
+            .unwrap_or_else(|| retention.group_by.join(" "));
+        let response = ui.add(
+            TextEdit::singleline(&mut text)
+                .hint_text("backup-*.tar.gz")
+                .desired_width(140.0),
+        );
+        if response.changed() {
+            retention.group_by = text.split_whitespace().map(String::from).collect();
+        }
+        if response.has_focus() {
+            ui.data_mut(|d| d.insert_temp(id, text));
+        } else {
+            ui.data_mut(|d| d.remove::<String>(id));
+        }
+    }
+
+    ui.checkbox(&mut folder.prune_empty_dirs, "Prune empty dirs");
+    if folder.prune_empty_dirs {
+        ui.label("unmodified for");
+        ui.add(
+            DragValue::new(&mut folder.prune_min_age_days)
+                .clamp_range(0..=3650)
+                .suffix(" days"),
+        );
+    }
+
+    ComboBox::from_id_source(("combine", index))
+        .selected_text(folder.combine.to_string())
+        .show_ui(ui, |ui| {
+            for combine in [RuleCombination::Any, RuleCombination::All] {
+                ui.selectable_value(&mut folder.combine, combine, combine.to_string());
+            }
+        });
+}
+
+/// Draws the editor for a folder's schedule and what the daemon does when it fires.
+///
+/// Daily times are edited as hour and minute. A cron expression that cannot be used is
+/// flagged next to its text field, with the reason on hover.
+///
+fn show_schedule(ui: &mut eframe::egui::Ui, index: usize, folder: &mut TrackedFolder) {
+    use eframe::egui::{Color32, ComboBox, DragValue, TextEdit};
+
+    let selected = match &folder.schedule {
+        None => "Manual",
+        Some(Schedule::EveryHours(_)) => "Every",
+        Some(Schedule::Daily(_)) => "Daily at",
+        Some(Schedule::Cron(_)) => "Cron",
+    };
+    ui.label("⏰ Schedule:");
+    ComboBox::from_id_source(("schedule", index))
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            let defaults = [
+                ("Manual", None),
+                ("Every", Some(Schedule::EveryHours(24))),
+                ("Daily at", Some(Schedule::Daily(NaiveTime::MIN))),
+                ("Cron", Some(Schedule::Cron("0 3 * * *".to_string()))),
+            ];
+            for (label, schedule) in defaults {
+                if ui.selectable_label(selected == label, label).clicked() && selected != label {
+                    folder.schedule = schedule;
+                }
+            }
+        });
+
+    match &mut folder.schedule {
+        None => return,
+        Some(Schedule::EveryHours(hours)) => {
+            ui.add(
+                DragValue::new(hours)
+                    .clamp_range(1..=24 * 365)
+                    .suffix(" hours"),
+            );
+        }
+        Some(Schedule::Daily(time)) => {
+            let mut hour = time.hour();
+            let mut minute = time.minute();
+            let edited = ui
+                .add(DragValue::new(&mut hour).clamp_range(0..=23))
+                .changed()
+                | ui.add(DragValue::new(&mut minute).clamp_range(0..=59))
+                    .changed();
+            if edited {
+                *time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or(*time);
+            }
+        }
+        Some(Schedule::Cron(expression)) => {
+            ui.add(
+                TextEdit::singleline(expression)
+                    .hint_text("min hour day month weekday")
+                    .desired_width(140.0),
+            );
+        }
+    }
+    if let Some(Err(e)) = folder.schedule.as_ref().map(Schedule::validate) {
+        ui.colored_label(Color32::RED, "⚠").on_hover_text(e);
+    }
+
+    ComboBox::from_id_source(("scheduled_action", index))
+        .selected_text(folder.scheduled_action.to_string())
+        .show_ui(ui, |ui| {
+            for action in [ScheduledAction::Notify, ScheduledAction::Clean] {
+                ui.selectable_value(&mut folder.scheduled_action, action, action.to_string());
+            }
+        });
+}
+
+/// Formats a folder's patterns for the pattern editor, one per line.
+///
+/// Include patterns are listed first, followed by exclude patterns prefixed with `!`.
+///
+fn patterns_to_text(folder: &TrackedFolder) -> String {
+    folder
+        .include
+        .iter()
+        .cloned()
+        .chain(folder.exclude.iter().map(|p| format!("!{}", p)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses pattern editor text into include and exclude lists.
+///
+/// Blank lines are ignored and lines starting with `!` become exclude patterns.
+///
+/// # Returns
+///
+/// - A tuple of `(include, exclude)` patterns.
+///
+fn patterns_from_text(text: &str) -> (Vec<String>, Vec<String>) {
+    let mut include = Vec::new();
+    let mut exclude = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        match line.strip_prefix('!') {
+            Some(pattern) => exclude.push(pattern.to_string()),
+            None => include.push(line.to_string()),
+        }
+    }
+    (include, exclude)
+}
+
+impl AutoCleanerApp {
+    /// Saves the config once its latest change has settled, see [`AutoSave::update`],
+    /// and schedules a repaint for when the pending change is due.
+    ///
+    /// Saving the empty config used in place of a broken file would overwrite the
+    /// user's folders, so nothing is written until the file loads again.
+    ///
+    fn autosave_config(&mut self, ctx: &eframe::egui::Context) {
+        if self.config_error.is_some() {
+            return;
+        }
+        let now = Instant::now();
+        let result = self.autosave.update(&self.config, now, save_config);
+        self.report_save(result);
+
+        if let Some(deadline) = self.autosave.deadline() {
+            ctx.request_repaint_after(deadline.saturating_duration_since(now));
+        }
+    }
+
+    /// Saves any unsaved change to the config right away, e.g. when the window closes.
+    fn flush_config(&mut self) {
+        if self.config_error.is_some() {
+            return;
+        }
+        let result = self.autosave.flush(&self.config, save_config);
+        self.report_save(result);
+    }
+
+    /// Takes in a config file that was changed on disk, e.g. by the CLI or another
+    /// instance.
+    ///
+    /// Without unsaved edits, the file is used as it is. Otherwise it is merged with them
+    /// by [`merge_configs`], keeping the unsaved edit where both changed the same folder
+    /// or setting. The merged config is then saved like any edit, which keeps the file's
+    /// version as the newest config backup. Changes made by this app's own saves are
+    /// ignored.
+    ///
+    /// A file that no longer loads sets `config_error`, so it is not overwritten.
+    ///
+    fn reload_external_config(&mut self) {
+        let remote = match FolderConfig::load_or_recover() {
+            Ok((remote, recovery)) => {
+                if let Some(recovery) = recovery {
+                    self.log
+                        .push_str(&format!("♻ Recovered the config: {}\n", recovery));
+                }
+                remote
+            }
+            Err(e) => {
+                let error = e.to_string();
+                if self.config_error.as_ref() != Some(&error) {
+                    self.log.push_str(&format!(
+                        "⚠ The config changed on disk but could not be loaded: {}\n",
+                        error
+                    ));
+                    self.config_error = Some(error);
+                }
+                return;
+            }
+        };
+        if self.config_error.take().is_some() {
+            self.log.push_str("✔ Config loaded\n");
+        }
+        if remote == *self.autosave.saved() {
+            return;
+        }
+
+        let (merged, conflicts) = merge_configs(self.autosave.saved(), &self.config, &remote);
+        if self.autosave.is_dirty(&self.config) {
+            self.log
+                .push_str("🔀 Merged config changes from another process into unsaved edits\n");
+        } else {
+            self.log
+                .push_str("🔄 Config reloaded after a change by another process\n");
+        }
+        if !conflicts.is_empty() {
+            self.log.push_str(&format!(
+                "⚠ Kept the unsaved edits to {}, the other version is kept as a config backup\n",
+                conflicts.join(", ")
+            ));
+        }
+
+        let find = |config: &FolderConfig, path: &str| {
+            config.folders.iter().find(|f| f.path == path).cloned()
+        };
+        self.plans
+            .retain(|path, _| find(&self.config, path) == find(&merged, path));
+        self.autosave = AutoSave::new(remote, SAVE_DELAY);
+        self.config = merged;
+    }
+
+    /// Reloads the quarantine manifest shown in the quarantine section, logging why if it
+    /// cannot be loaded.
+    fn reload_quarantine(&mut self) {
+        match QuarantineManifest::load() {
+            Ok(manifest) => self.quarantine = manifest,
+            Err(e) => self.log.push_str(&format!(
+                "⚠ Failed to load the quarantine manifest: {}\n",
+                e
+            )),
+        }
+    }
+
+    /// Logs a failed save once, until a save succeeds or fails differently.
+    fn report_save(&mut self, result: io::Result<bool>) {
+        match result {
+            Ok(true) => self.save_error = None,
+            Ok(false) => {}
+            Err(e) => {
+                let error = e.to_string();
+                if self.save_error.as_ref() != Some(&error) {
+                    self.log
+                        .push_str(&format!("⚠ Failed to save the config: {}\n", error));
+                    self.save_error = Some(error);
+                }
+            }
+        }
+    }
+
+    /// Draws why the config could not be loaded, if it could not, with a button to load
+    /// it again after the file was fixed.
+    fn show_config_error(&mut self, ui: &mut eframe::egui::Ui) {
+        let Some(error) = &self.config_error else {
+            return;
+        };
+
+        ui.colored_label(
+            eframe::egui::Color32::RED,
+            format!(
+                "⚠ The config could not be loaded, changes are not saved: {}",
+                error
+            ),
+        );
+        if ui.button("🔄 Reload config").clicked() {
+            match FolderConfig::load_or_recover() {
+                Ok((config, recovery)) => {
+                    self.autosave = AutoSave::new(config.clone(), SAVE_DELAY);
+                    self.config = config;
+                    self.config_error = None;
+                    self.plans.clear();
+                    match recovery {
+                        Some(recovery) => self
+                            .log
+                            .push_str(&format!("♻ Recovered the config: {}\n", recovery)),
+                        None => self.log.push_str("✔ Config loaded\n"),
+                    }
+                }
+                Err(e) => self.config_error = Some(e.to_string()),
+            }
+        }
+    }
+
+    /// Collects the output of every finished background task.
+    ///
+    /// Finished scans are logged and their plan is kept for the preview, unless the
+    /// folder's settings changed or it was untracked while the scan was running. Finished
+    /// cleanups, purges, restores and undos are logged.
+    ///
+    fn poll_tasks(&mut self) {
+        let finished: Vec<_> = self
+            .tasks
+            .iter_mut()
+            .filter_map(|(path, task)| Some((path.clone(), task.try_finish()?)))
+            .collect();
+
+        for (path, output) in finished {
+            let Some(task) = self.tasks.remove(&path) else {
+                continue;
+            };
+            let current = task
+                .folder
+                .as_ref()
+                .is_some_and(|folder| self.config.folders.contains(folder));
+
+            match output {
+                Ok(TaskOutput::Scan { plan, .. }) if plan.cancelled => {
+                    self.log.push_str(&format!(
+                        "⏹ Scan of {} cancelled after {} files\n",
+                        path,
+                        task.progress.snapshot().visited
+                    ));
+                }
+                Ok(TaskOutput::Scan { plan, journal }) => {
+                    log_plan(&mut self.log, &plan);
+                    self.log_journal(journal);
+                    if !current {
+                        self.log.push_str(&format!(
+                            "⚠ {} changed during the scan, scan it again\n",
+                            path
+                        ));
+                    } else {
+                        self.plans.insert(path, PlanPreview::new(plan));
+                    }
+                }
+                Ok(TaskOutput::Cleanup {
+                    plan,
+                    report,
+                    journal,
+                }) => {
+                    log_report(&mut self.log, &plan, &report);
+                    self.log_journal(journal);
+                    if plan.delete_mode == DeleteMode::Quarantine {
+                        self.reload_quarantine();
+                    }
+                }
+                Ok(TaskOutput::Purge { purged }) => {
+                    match purged {
+                        Ok(count) => self
+                            .log
+                            .push_str(&format!("🧹 Purged {} quarantined files\n", count)),
+                        Err(e) => self
+                            .log
+                            .push_str(&format!("⚠ Failed to purge quarantine: {}\n", e)),
+                    }
+                    self.reload_quarantine();
+                }
+                Ok(TaskOutput::Restore { restored }) => {
+                    match restored {
+                        Ok(path) => self
+                            .log
+                            .push_str(&format!("↩ Restored: {}\n", path.display())),
+                        Err(e) => self.log.push_str(&format!("⚠ Restore failed: {}\n", e)),
+                    }
+                    self.reload_quarantine();
+                }
+                Ok(TaskOutput::Undo { report }) => self.log_undo(report),
+                Err(_) => {
+                    self.log
+                        .push_str(&format!("⚠ Background task for {} panicked\n", path));
+                }
+            }
+        }
+    }
+
+    /// Logs a failure to record a run in the journal, and otherwise makes the history view
+    /// read the journal again.
+    fn log_journal(&mut self, journal: std::io::Result<()>) {
+        match journal {
+            Ok(()) => self.history = None,
+            Err(e) => self
+                .log
+                .push_str(&format!("⚠ Failed to write journal: {}\n", e)),
+        }
+    }
+
+    /// Logs the outcome of undoing the most recent cleanup, including every file left in
+    /// place because its original path is taken again.
+    fn log_undo(&mut self, report: io::Result<Option<UndoReport>>) {
+        let report = match report {
+            Ok(Some(report)) if report.not_undoable => {
+                self.log.push_str(&format!(
+                    "ℹ The last cleanup of {} cannot be undone: {}\n",
+                    report.folder,
+                    report.summary()
+                ));
+                return;
+            }
+            Ok(Some(report)) => report,
+            Ok(None) => {
+                self.log.push_str("ℹ No cleanup to undo\n");
+                return;
+            }
+            Err(e) => {
+                self.log.push_str(&format!(
+                    "⚠ Files were restored, but the undo could not be recorded: {}\n",
+                    e
+                ));
+                self.reload_quarantine();
+                return;
+            }
+        };
+
+        self.log.push_str(&format!(
+            "↶ Undid cleanup of {}: {}\n",
+            report.folder,
+            report.summary()
+        ));
+        for path in report.conflicts.iter().take(MAX_LOGGED_ENTRIES) {
+            self.log.push_str(&format!(
+                "⚠ Kept in the trash or quarantine, {} exists again\n",
+                path.display()
+            ));
+        }
+        if report.conflicts.len() > MAX_LOGGED_ENTRIES {
+            self.log.push_str(&format!(
+                "⚠ … and {} more conflicts\n",
+                report.conflicts.len() - MAX_LOGGED_ENTRIES
+            ));
+        }
+        log_errors(&mut self.log, &report.failed);
+
+        self.reload_quarantine();
+        self.history = None;
+    }
+
+    /// Draws the history section listing past runs recorded in the journal.
+    ///
+    /// The journal is read when the section is first opened and after every run, and
+    /// each run can be expanded to list its files.
+    ///
+    fn show_history(&mut self, ui: &mut eframe::egui::Ui) {
+        use eframe::egui::{CollapsingHeader, ScrollArea};
+
+        CollapsingHeader::new("📜 History").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("🔄 Refresh").clicked() {
+                    self.history = None;
+                }
+                if ui
+                    .add_enabled(
+                        self.tasks.is_empty(),
+                        eframe::egui::Button::new("↶ Undo last cleanup"),
+                    )
+                    .on_hover_text(
+                        "Restore the files of the last cleanup from the trash or quarantine",
+                    )
+                    .on_disabled_hover_text("Wait for running scans and cleanups to finish")
+                    .clicked()
+                {
+                    let ctx = ui.ctx().clone();
+                    let task = BackgroundTask::undo(move || ctx.request_repaint());
+                    self.tasks.insert(QUARANTINE_TASK.to_string(), task);
+                }
+            });
+            let runs = self
+                .history
+                .get_or_insert_with(|| load_runs(MAX_HISTORY_RUNS));
+
+            if runs.is_empty() {
+                ui.label("No scans or cleanups recorded yet.");
+            }
+
+            ScrollArea::vertical()
+                .id_source("history")
+                .max_height(250.0)
+                .show(ui, |ui| {
+                    for run in runs.iter() {
+                        CollapsingHeader::new(format!(
+                            "{} {} — {}: {}{}",
+                            run.timestamp
+                                .with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M"),
+                            run.kind,
+                            run.folder,
+                            run.summary(),
+                            if run.reverted { " (reverted)" } else { "" }
+                        ))
+                        .id_source(("run", &run.id))
+                        .show(ui, |ui| {
+                            for entry in run.entries.iter().take(MAX_LOGGED_ENTRIES) {
+                                ui.label(format!(
+                                    "{} {} ({} bytes{}){}",
+                                    entry.outcome,
+                                    entry.path.display(),
+                                    entry.size.unwrap_or_default(),
+                                    entry
+                                        .rule
+                                        .as_ref()
+                                        .map(|rule| format!(", {}", rule))
+                                        .unwrap_or_default(),
+                                    entry
+                                        .message
+                                        .as_ref()
+                                        .map(|message| format!(": {}", message))
+                                        .unwrap_or_default()
+                                ));
+                            }
+                            if run.entries.len() > MAX_LOGGED_ENTRIES {
+                                ui.label(format!(
+                                    "… and {} more",
+                                    run.entries.len() - MAX_LOGGED_ENTRIES
+                                ));
+                            }
+                        });
+                    }
+                });
+        });
+    }
+
+    /// Draws the quarantine section listing quarantined files.
+    ///
+    /// Each file can be restored to its original location. The grace period slider
+    /// controls when [`purge_expired`](autocleaner_core::quarantine::purge_expired)
+    /// permanently deletes quarantined files. Restores and purges run as a
+    /// [`BackgroundTask`] once no other task is running.
+    ///
+    fn show_quarantine(&mut self, ui: &mut eframe::egui::Ui) {
+        use eframe::egui::{CollapsingHeader, ScrollArea, Slider};
+
+        CollapsingHeader::new(format!(
+            "🛡 Quarantine ({} files)",
+            self.quarantine.entries.len()
+        ))
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Purge after days:");
+                ui.add(Slider::new(&mut self.config.quarantine_days, 1..=365));
+
+                if ui
+                    .add_enabled(
+                        self.tasks.is_empty(),
+                        eframe::egui::Button::new("🧹 Purge expired"),
+                    )
+                    .on_disabled_hover_text("Wait for running scans and cleanups to finish")
+                    .clicked()
+                {
+                    let task = BackgroundTask::purge(self.config.quarantine_days, ui.ctx());
+                    self.tasks.insert(QUARANTINE_TASK.to_string(), task);
+                }
+                if let Some(task) = self.tasks.get(QUARANTINE_TASK) {
+                    ui.spinner();
+                    ui.label(task.status());
+                }
+
+                if ui.button("🔄 Refresh").clicked() {
+                    self.reload_quarantine();
+                }
+            });
+
+            let idle = self.tasks.is_empty();
+            let mut to_restore = None;
+            ScrollArea::vertical()
+                .id_source("quarantine")
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    for entry in self.quarantine.entries.iter().rev() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(idle, eframe::egui::Button::new("↩ Restore"))
+                                .on_disabled_hover_text(
+                                    "Wait for running scans and cleanups to finish",
+                                )
+                                .clicked()
+                            {
+                                to_restore = Some(entry.stored_path.clone());
+                            }
+                            ui.label(format!(
+                                "{} — {} bytes, quarantined {}",
+                                entry.original_path.display(),
+                                entry.size,
+                                entry.quarantined_at.format("%Y-%m-%d %H:%M")
+                            ));
+                        });
+                    }
+                });
+
+            if let Some(stored_path) = to_restore {
+                let task = BackgroundTask::restore(stored_path, ui.ctx());
+                self.tasks.insert(QUARANTINE_TASK.to_string(), task);
+            }
+        });
     }
 }
@@ -0,0 +1,244 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// How long a value must stay unchanged before [`AutoSave::update`] writes it.
+pub const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Tracks changes to a value edited in place and decides when to write it.
+///
+/// The value is compared against the last written copy, so it is only written when it
+/// actually changed. A change is written once the value has stayed the same for the
+/// debounce delay, so a slider drag causes a single write when it ends rather than one
+/// per frame.
+///
+/// # Fields
+///
+/// - `saved`: The value as last written or loaded.
+/// - `pending`: The changed value and when it last changed, while it is not yet written.
+/// - `delay`: How long a change must settle before it is written.
+///
+pub struct AutoSave<T> {
+    saved: T,
+    pending: Option<(T, Instant)>,
+    delay: Duration,
+}
+
+impl<T: Clone + PartialEq> AutoSave<T> {
+    /// Starts tracking a value that matches what is stored.
+    ///
+    /// # Parameters
+    ///
+    /// - `saved`: The value as currently stored.
+    /// - `delay`: How long a change must settle before it is written.
+    ///
+    pub fn new(saved: T, delay: Duration) -> Self {
+        Self {
+            saved,
+            pending: None,
+            delay,
+        }
+    }
+
+    /// Returns the value as last written or loaded.
+    pub fn saved(&self) -> &T {
+        &self.saved
+    }
+
+    /// Checks whether `current` differs from the value last written.
+    pub fn is_dirty(&self, current: &T) -> bool {
+        *current != self.saved
+    }
+
+    /// Returns when the pending change is due to be written, if there is one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending
+            .as_ref()
+            .map(|(_, changed_at)| *changed_at + self.delay)
+    }
+
+    /// Records the current value and writes it if its change has settled.
+    ///
+    /// A change restarts the delay. A failed write is retried once the delay passed
+    /// again.
+    ///
+    /// # Parameters
+    ///
+    /// - `current`: The value as currently edited.
+    /// - `now`: The current time.
+    /// - `save`: Writes the value.
+    ///
+    /// # Returns
+    ///
+    /// - `true` if the value was written, or the error `save` returned.
+    ///
+    pub fn update(
+        &mut self,
+        current: &T,
+        now: Instant,
+        save: impl FnOnce(&T) -> io::Result<()>,
+    ) -> io::Result<bool> {
+        if !self.is_dirty(current) {
+            self.pending = None;
+            return Ok(false);
+        }
+
+        match &self.pending {
+            Some((pending, changed_at)) if pending == current => {
+                if now.duration_since(*changed_at) < self.delay {
+                    return Ok(false);
+                }
+            }
+            _ => {
+                self.pending = Some((current.clone(), now));
+                return Ok(false);
+            }
+        }
+
+        if let Err(e) = save(current) {
+            self.pending = Some((current.clone(), now));
+            return Err(e);
+        }
+        self.saved = current.clone();
+        self.pending = None;
+        Ok(true)
+    }
+
+    /// Writes the current value right away if it differs from the value last written.
+    ///
+    /// # Parameters
+    ///
+    /// - `current`: The value as currently edited.
+    /// - `save`: Writes the value.
+    ///
+    /// # Returns
+    ///
+    /// - `true` if the value was written, or the error `save` returned.
+    ///
+    pub fn flush(
+        &mut self,
+        current: &T,
+        save: impl FnOnce(&T) -> io::Result<()>,
+    ) -> io::Result<bool> {
+        self.pending = None;
+        if !self.is_dirty(current) {
+            return Ok(false);
+        }
+        save(current)?;
+        self.saved = current.clone();
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Returns a writer that counts its calls.
+    fn counting(writes: &Cell<usize>) -> impl Fn(&u32) -> io::Result<()> + '_ {
+        move |_| {
+            writes.set(writes.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn unchanged_value_is_never_written() {
+        let writes = Cell::new(0);
+        let mut autosave = AutoSave::new(7, SAVE_DELAY);
+        let start = Instant::now();
+
+        for frame in 0..100 {
+            let now = start + Duration::from_millis(frame * 16);
+            autosave.update(&7, now, counting(&writes)).unwrap();
+        }
+        autosave.flush(&7, counting(&writes)).unwrap();
+
+        assert_eq!(writes.get(), 0);
+        assert_eq!(autosave.deadline(), None);
+    }
+
+    #[test]
+    fn drag_is_written_once_after_it_settles() {
+        let writes = Cell::new(0);
+        let mut autosave = AutoSave::new(0, SAVE_DELAY);
+        let start = Instant::now();
+
+        // A slider dragged from 1 to 30, one step per frame.
+        for value in 1..=30 {
+            let now = start + Duration::from_millis(value as u64 * 16);
+            autosave.update(&value, now, counting(&writes)).unwrap();
+        }
+        assert_eq!(writes.get(), 0);
+
+        let settled = start + Duration::from_millis(30 * 16) + SAVE_DELAY;
+        assert_eq!(autosave.deadline(), Some(settled));
+        assert!(autosave.update(&30, settled, counting(&writes)).unwrap());
+        autosave
+            .update(&30, settled + SAVE_DELAY, counting(&writes))
+            .unwrap();
+
+        assert_eq!(writes.get(), 1);
+        assert!(!autosave.is_dirty(&30));
+    }
+
+    #[test]
+    fn reverted_change_is_not_written() {
+        let writes = Cell::new(0);
+        let mut autosave = AutoSave::new(1, SAVE_DELAY);
+        let start = Instant::now();
+
+        autosave.update(&2, start, counting(&writes)).unwrap();
+        autosave
+            .update(&1, start + SAVE_DELAY, counting(&writes))
+            .unwrap();
+        autosave
+            .update(&1, start + SAVE_DELAY * 2, counting(&writes))
+            .unwrap();
+
+        assert_eq!(writes.get(), 0);
+    }
+
+    #[test]
+    fn flush_writes_pending_change_once() {
+        let writes = Cell::new(0);
+        let mut autosave = AutoSave::new(1, SAVE_DELAY);
+        let start = Instant::now();
+
+        autosave.update(&2, start, counting(&writes)).unwrap();
+        assert!(autosave.flush(&2, counting(&writes)).unwrap());
+        assert!(!autosave.flush(&2, counting(&writes)).unwrap());
+        autosave
+            .update(&2, start + SAVE_DELAY, counting(&writes))
+            .unwrap();
+
+        assert_eq!(writes.get(), 1);
+    }
+
+    #[test]
+    fn failed_write_is_retried_after_the_delay() {
+        let attempts = Cell::new(0);
+        let failing = |_: &u32| {
+            attempts.set(attempts.get() + 1);
+            Err(io::Error::other("disk full"))
+        };
+        let mut autosave = AutoSave::new(1, SAVE_DELAY);
+        let start = Instant::now();
+
+        autosave.update(&2, start, failing).unwrap();
+        assert!(autosave.update(&2, start + SAVE_DELAY, failing).is_err());
+        autosave
+            .update(&2, start + SAVE_DELAY + Duration::from_millis(16), failing)
+            .unwrap();
+        assert_eq!(attempts.get(), 1);
+
+        let writes = Cell::new(0);
+        autosave
+            .update(&2, start + SAVE_DELAY * 2, counting(&writes))
+            .unwrap();
+        assert_eq!(writes.get(), 1);
+        assert!(!autosave.is_dirty(&2));
+    }
+}
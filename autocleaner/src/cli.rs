@@ -0,0 +1,705 @@
+#[cfg(target_os = "linux")]
+use crate::{startup::resolve_startup_target, systemd};
+use autocleaner_core::{
+    cleanup::{remove_old_files, scan_folder, CleanupError, CleanupPlan, CleanupReport},
+    config::{get_config_path, save_config, FolderConfig, ScheduledAction, TrackedFolder},
+    journal::{load_runs, record_cleanup, record_scan, JournalRun},
+    progress::Progress,
+    quarantine::{purge_expired, restore_original},
+    schedule::{Schedule, ScheduleState},
+};
+use chrono::NaiveTime;
+use clap::{Args, Parser, Subcommand};
+use serde_json::{json, Value};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+/// The modes that used to be selected with a leading `--`, e.g. `--tray-startup` in the
+/// startup shortcut. They are still accepted in that form.
+const LEGACY_MODES: [&str; 5] = ["gui", "tray", "tray-startup", "restore", "purge"];
+
+/// The command line of AutoCleaner.
+///
+/// Without a subcommand, the GUI and the tray icon are launched.
+///
+/// # Exit Codes
+///
+/// - `0`: The command succeeded.
+/// - `1`: The command failed, e.g. because a folder is not tracked or the config could not
+///   be saved.
+/// - `2`: The command line could not be parsed.
+/// - `3`: The command ran, but some paths could not be scanned, removed or restored.
+///
+/// # Fields
+///
+/// - `json`: Print machine-readable JSON to stdout instead of text.
+/// - `command`: The subcommand to run.
+///
+#[derive(Parser)]
+#[command(
+    name = "autocleaner",
+    version,
+    about = "Removes old files from tracked folders",
+    long_about = None,
+    after_help = EXIT_CODES_HELP
+)]
+pub struct Cli {
+    #[arg(long, global = true, help = "Print JSON instead of text")]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// A subcommand of [`Cli`].
+#[derive(Subcommand)]
+pub enum Command {
+    /// List the files a cleanup would remove, for every tracked folder or only PATH.
+    Scan { path: Option<String> },
+    /// Remove old files from every tracked folder or only PATH.
+    Clean {
+        path: Option<String>,
+        /// Only list the files that would be removed, like `scan`.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List the tracked folders.
+    List,
+    /// Start tracking a folder.
+    Add {
+        path: String,
+        /// Remove files older than this many days.
+        #[arg(long)]
+        days: u32,
+        #[command(flatten)]
+        schedule: ScheduleArgs,
+        /// Let the daemon remove the files it finds instead of only notifying.
+        #[arg(long, requires = "schedule")]
+        auto_clean: bool,
+    },
+    /// Stop tracking a folder.
+    Remove { path: String },
+    /// Inspect the configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// List past scans, cleanups and undos recorded in the journal, newest first.
+    History {
+        /// The number of runs to list.
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Restore quarantined files to their original paths.
+    Restore {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Permanently delete quarantined files whose grace period has passed.
+    Purge,
+    /// Manage the systemd user units that run `autocleaner clean` on a timer. Linux only.
+    Systemd {
+        #[command(subcommand)]
+        command: SystemdCommand,
+    },
+    /// Keep running and scan or clean each scheduled folder when it is due.
+    Daemon,
+    /// Launch the graphical user interface.
+    Gui,
+    /// Start the system tray icon.
+    Tray,
+    /// Purge the quarantine, scan with a notification, then start the tray icon. Used at
+    /// system startup.
+    TrayStartup,
+}
+
+/// The schedule of a folder added with `autocleaner add`. At most one can be given.
+#[derive(Args)]
+#[group(id = "schedule", multiple = false)]
+pub struct ScheduleArgs {
+    /// Let the daemon evaluate the folder every N hours.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    every_hours: Option<u32>,
+    /// Let the daemon evaluate the folder daily at this local time.
+    #[arg(long, value_name = "HH:MM", value_parser = parse_time)]
+    daily: Option<NaiveTime>,
+    /// Let the daemon evaluate the folder on a cron schedule, e.g. "0 3 * * *".
+    #[arg(long, value_name = "EXPR")]
+    cron: Option<String>,
+}
+
+impl ScheduleArgs {
+    /// Returns the schedule that was given, if any.
+    fn schedule(&self) -> Option<Schedule> {
+        self.every_hours
+            .map(Schedule::EveryHours)
+            .or(self.daily.map(Schedule::Daily))
+            .or(self.cron.clone().map(Schedule::Cron))
+    }
+}
+
+/// Parses a local time of day given as `HH:MM`.
+fn parse_time(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text, "%H:%M").map_err(|e| format!("expected HH:MM: {}", e))
+}
+
+/// A subcommand of `autocleaner config`.
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the config file's path and contents.
+    Show,
+}
+
+/// A subcommand of `autocleaner systemd`.
+#[derive(Subcommand)]
+pub enum SystemdCommand {
+    /// Write autocleaner.service and autocleaner.timer to ~/.config/systemd/user and
+    /// enable the timer.
+    Install {
+        /// When the timer runs, as a systemd OnCalendar= expression.
+        #[arg(long, value_name = "EXPR", default_value = "daily", value_parser = parse_on_calendar)]
+        on_calendar: String,
+    },
+    /// Disable the timer and remove both units.
+    Uninstall,
+}
+
+/// Rejects `OnCalendar=` expressions that would break out of their line in the timer unit.
+fn parse_on_calendar(text: &str) -> Result<String, String> {
+    if text.trim().is_empty() || text.contains(['\n', '\r']) {
+        Err("expected a single-line OnCalendar= expression".to_string())
+    } else {
+        Ok(text.trim().to_string())
+    }
+}
+
+/// The exit code of a command that ran, but failed for some paths.
+const EXIT_PARTIAL: u8 = 3;
+
+/// The exit codes listed at the end of `--help`.
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  Success
+  1  The command failed
+  2  Invalid command line
+  3  Some paths could not be scanned, removed or restored";
+
+/// Parses the process arguments, accepting the legacy `--gui` style modes as subcommands.
+///
+/// Prints the help or a usage error and exits if the arguments are invalid.
+///
+pub fn parse() -> Cli {
+    let args = env::args().enumerate().map(|(i, arg)| {
+        let mode = arg.trim_start_matches('-');
+        if i == 1 && LEGACY_MODES.contains(&mode) {
+            mode.to_string()
+        } else {
+            arg
+        }
+    });
+    Cli::parse_from(args)
+}
+
+/// Runs a headless subcommand and prints its outcome.
+///
+/// # Parameters
+///
+/// - `cli`: The parsed command line. Its command must not be `Gui`, `Tray`, `TrayStartup`
+///   or `Daemon`, which are handled by `main`.
+///
+/// # Returns
+///
+/// - The exit code described on [`Cli`].
+///
+pub fn run(cli: &Cli) -> ExitCode {
+    let result = match &cli.command {
+        Some(Command::Scan { path }) => scan(cli, path.as_deref()),
+        Some(Command::Clean { path, dry_run }) => clean(cli, path.as_deref(), *dry_run),
+        Some(Command::List) => list(cli),
+        Some(Command::Add {
+            path,
+            days,
+            schedule,
+            auto_clean,
+        }) => add(cli, path, *days, schedule.schedule(), *auto_clean),
+        Some(Command::Remove { path }) => remove(cli, path),
+        Some(Command::Config {
+            command: ConfigCommand::Show,
+        }) => config_show(cli),
+        Some(Command::History { limit }) => history(cli, *limit),
+        Some(Command::Restore { paths }) => restore(cli, paths),
+        Some(Command::Purge) => purge(cli),
+        #[cfg(target_os = "linux")]
+        Some(Command::Systemd { command }) => systemd(cli, command),
+        #[cfg(not(target_os = "linux"))]
+        Some(Command::Systemd { .. }) => {
+            Err("systemd units are only available on Linux".to_string())
+        }
+        Some(Command::Gui | Command::Tray | Command::TrayStartup | Command::Daemon) | None => {
+            Err("this mode is not a headless command".to_string())
+        }
+    };
+
+    match result {
+        Ok(Outcome::Success) => ExitCode::SUCCESS,
+        Ok(Outcome::Partial) => ExitCode::from(EXIT_PARTIAL),
+        Err(message) => {
+            if cli.json {
+                println!("{}", json!({ "error": message }));
+            } else {
+                eprintln!("error: {}", message);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// How a command that did not fail outright went.
+enum Outcome {
+    Success,
+    Partial,
+}
+
+impl Outcome {
+    /// Returns `Partial` if any path failed, and `Success` otherwise.
+    fn from_failures(failures: usize) -> Self {
+        if failures == 0 {
+            Outcome::Success
+        } else {
+            Outcome::Partial
+        }
+    }
+}
+
+/// The result of a command, or the message to report if it failed.
+type CommandResult = Result<Outcome, String>;
+
+/// Loads the config, or returns why it cannot be loaded. A recovery from a backup is
+/// reported on stderr.
+fn load_config() -> Result<FolderConfig, String> {
+    let (config, recovery) =
+        FolderConfig::load_or_recover().map_err(|e| format!("cannot load the config: {}", e))?;
+    if let Some(recovery) = recovery {
+        eprintln!("warning: recovered the config: {}", recovery);
+    }
+    Ok(config)
+}
+
+/// Returns the tracked folders a command operates on.
+///
+/// # Parameters
+///
+/// - `config`: The loaded configuration.
+/// - `path`: The tracked folder asked for, or `None` for all of them.
+///
+/// # Returns
+///
+/// - The selected folders, or an `Err` if `path` is not tracked.
+///
+fn select_folders(config: &FolderConfig, path: Option<&str>) -> Result<Vec<TrackedFolder>, String> {
+    match path {
+        None => Ok(config.folders.clone()),
+        Some(path) => find_folder(config, path)
+            .map(|index| vec![config.folders[index].clone()])
+            .ok_or_else(|| format!("{} is not a tracked folder", path)),
+    }
+}
+
+/// Returns the index of the tracked folder at `path`, ignoring trailing separators.
+///
+/// A relative `path` is made absolute first, the same way `autocleaner add` stores it.
+///
+fn find_folder(config: &FolderConfig, path: &str) -> Option<usize> {
+    let path = std::path::absolute(path).ok()?;
+    config
+        .folders
+        .iter()
+        .position(|f| Path::new(&f.path) == path)
+}
+
+/// Prints a plan of every selected folder. `autocleaner scan`.
+fn scan(cli: &Cli, path: Option<&str>) -> CommandResult {
+    let config = load_config()?;
+    let folders = select_folders(&config, path)?;
+
+    let mut plans = Vec::new();
+    for folder in &folders {
+        let plan = scan_folder(folder, config.scan_workers, &Progress::default());
+        if let Err(e) = record_scan(&plan) {
+            eprintln!("warning: failed to write journal: {}", e);
+        }
+        plans.push(plan);
+    }
+
+    if cli.json {
+        let plans: Vec<Value> = plans.iter().map(plan_json).collect();
+        println!("{}", Value::Array(plans));
+    } else {
+        for plan in &plans {
+            print_plan(plan);
+        }
+    }
+
+    Ok(Outcome::from_failures(
+        plans.iter().map(|p| p.errors.len()).sum(),
+    ))
+}
+
+/// Removes the planned files of every selected folder, or only prints the plans with
+/// `--dry-run`. `autocleaner clean`.
+fn clean(cli: &Cli, path: Option<&str>, dry_run: bool) -> CommandResult {
+    if dry_run {
+        return scan(cli, path);
+    }
+
+    let config = load_config()?;
+    let folders = select_folders(&config, path)?;
+
+    let mut results = Vec::new();
+    for folder in &folders {
+        let plan = scan_folder(folder, config.scan_workers, &Progress::default());
+        let report = remove_old_files(&plan, &Progress::default());
+        if let Err(e) = record_cleanup(&plan, &report) {
+            eprintln!("warning: failed to write journal: {}", e);
+        }
+        results.push((plan, report));
+    }
+
+    if cli.json {
+        let reports: Vec<Value> = results
+            .iter()
+            .map(|(plan, report)| report_json(plan, report))
+            .collect();
+        println!("{}", Value::Array(reports));
+    } else {
+        for (plan, report) in &results {
+            print_report(plan, report);
+        }
+    }
+
+    Ok(Outcome::from_failures(
+        results
+            .iter()
+            .map(|(plan, report)| plan.errors.len() + report.failed.len())
+            .sum(),
+    ))
+}
+
+/// Prints the tracked folders. `autocleaner list`.
+fn list(cli: &Cli) -> CommandResult {
+    let config = load_config()?;
+
+    if cli.json {
+        println!("{}", json!(config.folders));
+    } else if config.folders.is_empty() {
+        println!("No folders are tracked.");
+    } else {
+        let state = ScheduleState::load().unwrap_or_else(|e| {
+            eprintln!("warning: the schedule state could not be loaded: {}", e);
+            ScheduleState::default()
+        });
+        for folder in &config.folders {
+            let schedule = match (&folder.schedule, state.folders.get(&folder.path)) {
+                (None, _) => "manual".to_string(),
+                (Some(schedule), None) => format!("{}, {}", schedule, folder.scheduled_action),
+                (Some(schedule), Some(run)) => format!(
+                    "{}, {}, next run {}",
+                    schedule,
+                    folder.scheduled_action,
+                    run.next_run
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                ),
+            };
+            println!(
+                "{}\t{} days\t{}\t{}",
+                folder.path, folder.days, folder.delete_mode, schedule
+            );
+        }
+    }
+    Ok(Outcome::Success)
+}
+
+/// Starts tracking a folder with default settings. `autocleaner add`.
+fn add(
+    cli: &Cli,
+    path: &str,
+    days: u32,
+    schedule: Option<Schedule>,
+    auto_clean: bool,
+) -> CommandResult {
+    if let Some(schedule) = &schedule {
+        schedule.validate()?;
+    }
+
+    let absolute = std::path::absolute(path).map_err(|e| format!("{}: {}", path, e))?;
+    if !absolute.is_dir() {
+        return Err(format!("{} is not a directory", absolute.display()));
+    }
+    let path = absolute.to_string_lossy().to_string();
+
+    let mut config = load_config()?;
+    if find_folder(&config, &path).is_some() {
+        return Err(format!("{} is already tracked", path));
+    }
+
+    let folder = TrackedFolder {
+        schedule,
+        scheduled_action: if auto_clean {
+            ScheduledAction::Clean
+        } else {
+            ScheduledAction::Notify
+        },
+        ..TrackedFolder::new(path, days)
+    };
+    if cli.json {
+        println!("{}", json!({ "added": folder }));
+    } else {
+        println!("Tracking {} (older than {} days)", folder.path, folder.days);
+        if let Some(schedule) = &folder.schedule {
+            println!("Scheduled {}: {}", schedule, folder.scheduled_action);
+        }
+    }
+    config.folders.push(folder);
+    save_config(&config).map_err(|e| format!("cannot save the config: {}", e))?;
+    Ok(Outcome::Success)
+}
+
+/// Stops tracking a folder. `autocleaner remove`.
+fn remove(cli: &Cli, path: &str) -> CommandResult {
+    let mut config = load_config()?;
+    let index =
+        find_folder(&config, path).ok_or_else(|| format!("{} is not a tracked folder", path))?;
+
+    if cli.json {
+        println!("{}", json!({ "removed": config.folders[index] }));
+    } else {
+        println!("No longer tracking {}", config.folders[index].path);
+    }
+    config.folders.remove(index);
+    save_config(&config).map_err(|e| format!("cannot save the config: {}", e))?;
+    Ok(Outcome::Success)
+}
+
+/// Prints the config file's path and contents. `autocleaner config show`.
+fn config_show(cli: &Cli) -> CommandResult {
+    let path = get_config_path();
+    let config = load_config()?;
+
+    if cli.json {
+        println!("{}", json!({ "path": path, "config": config }));
+    } else {
+        let contents = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        println!("# {}", path.display());
+        println!("{}", contents);
+    }
+    Ok(Outcome::Success)
+}
+
+/// Prints the most recent runs of the journal. `autocleaner history`.
+fn history(cli: &Cli, limit: usize) -> CommandResult {
+    let runs = load_runs(limit);
+
+    if cli.json {
+        let runs: Vec<Value> = runs.iter().map(run_json).collect();
+        println!("{}", Value::Array(runs));
+    } else if runs.is_empty() {
+        println!("No scans or cleanups recorded yet.");
+    } else {
+        for run in &runs {
+            println!(
+                "{}\t{}\t{}\t{}{}",
+                run.timestamp
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M"),
+                run.kind,
+                run.folder,
+                run.summary(),
+                if run.reverted { " (reverted)" } else { "" }
+            );
+        }
+    }
+    Ok(Outcome::Success)
+}
+
+/// Restores quarantined files to their original paths. `autocleaner restore`.
+fn restore(cli: &Cli, paths: &[PathBuf]) -> CommandResult {
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    for path in paths {
+        match restore_original(path) {
+            Ok(original) => restored.push(original),
+            Err(e) => failed.push(CleanupError::from_io(path, &e)),
+        }
+    }
+
+    if cli.json {
+        println!(
+            "{}",
+            json!({
+                "restored": restored,
+                "failed": failed.iter().map(error_json).collect::<Vec<_>>(),
+            })
+        );
+    } else {
+        for path in &restored {
+            println!("Restored {}", path.display());
+        }
+        for error in &failed {
+            eprintln!("Failed to restore {}", error);
+        }
+    }
+    Ok(Outcome::from_failures(failed.len()))
+}
+
+/// Permanently deletes expired quarantined files. `autocleaner purge`.
+fn purge(cli: &Cli) -> CommandResult {
+    let purged = purge_expired(load_config()?.quarantine_days).map_err(|e| e.to_string())?;
+
+    if cli.json {
+        println!("{}", json!({ "purged": purged }));
+    } else {
+        println!("Purged {} quarantined files", purged);
+    }
+    Ok(Outcome::Success)
+}
+
+/// Installs or removes the systemd user units. `autocleaner systemd`.
+#[cfg(target_os = "linux")]
+fn systemd(cli: &Cli, command: &SystemdCommand) -> CommandResult {
+    match command {
+        SystemdCommand::Install { on_calendar } => {
+            let exe_path = resolve_startup_target().map_err(|e| e.to_string())?;
+            let (service_path, timer_path) =
+                systemd::install_units(&exe_path, on_calendar).map_err(|e| e.to_string())?;
+            systemd::set_timer_enabled(true).map_err(|e| e.to_string())?;
+            if cli.json {
+                println!(
+                    "{}",
+                    json!({ "installed": [service_path, timer_path], "enabled": true })
+                );
+            } else {
+                println!("Installed {}", service_path.display());
+                println!("Installed {}", timer_path.display());
+                println!("Enabled {} ({})", systemd::TIMER_NAME, on_calendar);
+            }
+        }
+        SystemdCommand::Uninstall => {
+            let removed = systemd::uninstall_units().map_err(|e| e.to_string())?;
+            if cli.json {
+                println!("{}", json!({ "removed": removed }));
+            } else if removed.is_empty() {
+                println!("The systemd units are not installed.");
+            } else {
+                for path in &removed {
+                    println!("Removed {}", path.display());
+                }
+            }
+        }
+    }
+    Ok(Outcome::Success)
+}
+
+/// Prints a plan's files and errors as text.
+fn print_plan(plan: &CleanupPlan) {
+    println!(
+        "{}: {} files, {} bytes would be removed ({})",
+        plan.root,
+        plan.len(),
+        plan.total_size(),
+        plan.delete_mode
+    );
+    for file in &plan.files {
+        println!(
+            "  {}\t{} bytes\t{}",
+            file.path.display(),
+            file.size,
+            file.rule
+        );
+    }
+    for error in &plan.errors {
+        eprintln!("  {}", error);
+    }
+}
+
+/// Prints the outcome of a cleanup as text.
+fn print_report(plan: &CleanupPlan, report: &CleanupReport) {
+    println!(
+        "{}: {}, {} bytes reclaimed",
+        plan.root,
+        report.summary(),
+        report.deleted_size()
+    );
+    for removed in &report.deleted {
+        println!("  removed {}", removed.file.path.display());
+    }
+    for skipped in &report.skipped {
+        println!(
+            "  skipped {}: {}",
+            skipped.file.path.display(),
+            skipped.reason
+        );
+    }
+    for error in plan.errors.iter().chain(&report.failed) {
+        eprintln!("  {}", error);
+    }
+}
+
+/// Converts an error into a JSON object.
+fn error_json(error: &CleanupError) -> Value {
+    json!({
+        "path": error.path,
+        "kind": error.kind.to_string(),
+        "message": error.message,
+    })
+}
+
+/// Converts a plan into a JSON object.
+fn plan_json(plan: &CleanupPlan) -> Value {
+    json!({
+        "folder": plan.root,
+        "delete_mode": plan.delete_mode,
+        "total_size": plan.total_size(),
+        "files": plan.files.iter().map(|file| json!({
+            "path": file.path,
+            "size": file.size,
+            "modified": file.modified,
+            "rule": file.rule.to_string(),
+        })).collect::<Vec<_>>(),
+        "errors": plan.errors.iter().map(error_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Converts the outcome of a cleanup into a JSON object.
+fn report_json(plan: &CleanupPlan, report: &CleanupReport) -> Value {
+    json!({
+        "folder": plan.root,
+        "delete_mode": plan.delete_mode,
+        "deleted_size": report.deleted_size(),
+        "deleted": report.deleted.iter().map(|removed| json!({
+            "path": removed.file.path,
+            "size": removed.file.size,
+            "location": removed.location,
+        })).collect::<Vec<_>>(),
+        "skipped": report.skipped.iter().map(|skipped| json!({
+            "path": skipped.file.path,
+            "reason": skipped.reason,
+        })).collect::<Vec<_>>(),
+        "failed": plan.errors.iter().chain(&report.failed).map(error_json).collect::<Vec<_>>(),
+        "pruned_dirs": report.pruned_dirs,
+    })
+}
+
+/// Converts a journal run into a JSON object.
+fn run_json(run: &JournalRun) -> Value {
+    json!({
+        "id": run.id,
+        "kind": run.kind,
+        "folder": run.folder,
+        "timestamp": run.timestamp,
+        "reverted": run.reverted,
+        "summary": run.summary(),
+        "entries": run.entries,
+    })
+}
@@ -0,0 +1,228 @@
+use autocleaner_core::config::get_config_path;
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+#[cfg(target_os = "linux")]
+use std::{
+    ffi::{CString, OsString},
+    fs::File,
+    io::Read,
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsRawFd, FromRawFd},
+    },
+};
+
+#[cfg(not(target_os = "linux"))]
+use std::{thread, time::SystemTime};
+
+/// How often the config file is checked for changes where it cannot be watched.
+#[cfg(not(target_os = "linux"))]
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the config file for changes, whether made by another process or this one.
+///
+/// On Linux, the config directory is watched with inotify, so a file replaced by a
+/// rename (as [`save_config`](autocleaner_core::config::save_config) does) is noticed
+/// as well as one written in place.
+///
+/// # Fields
+///
+/// - `inotify`: The inotify instance watching the config directory.
+/// - `file_name`: The file name of the config file within that directory.
+///
+#[cfg(target_os = "linux")]
+pub struct ConfigWatcher {
+    inotify: File,
+    file_name: OsString,
+}
+
+/// Watches the config file for changes, whether made by another process or this one.
+///
+/// Without inotify, the file's modification time and size are compared on every check.
+///
+/// # Fields
+///
+/// - `config_path`: The config file.
+/// - `stamp`: The modification time and size of the file when it was last checked.
+///
+#[cfg(not(target_os = "linux"))]
+pub struct ConfigWatcher {
+    config_path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl ConfigWatcher {
+    /// Starts watching the config file. The config directory is created if needed.
+    ///
+    /// # Returns
+    ///
+    /// - The watcher, or an `Err` if the config directory cannot be watched.
+    ///
+    #[cfg(target_os = "linux")]
+    pub fn new() -> io::Result<Self> {
+        let (config_dir, file_name) = config_location()?;
+        fs::create_dir_all(&config_dir)?;
+
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` was just created and is owned by nothing else.
+        let inotify = unsafe { File::from_raw_fd(fd) };
+
+        let config_dir = CString::new(config_dir.as_os_str().as_bytes())?;
+        let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO;
+        if unsafe { libc::inotify_add_watch(fd, config_dir.as_ptr(), mask) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { inotify, file_name })
+    }
+
+    /// Starts watching the config file.
+    ///
+    /// # Returns
+    ///
+    /// - The watcher. Creating it does not fail on this platform.
+    ///
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> io::Result<Self> {
+        let config_path = get_config_path();
+        Ok(Self {
+            stamp: file_stamp(&config_path),
+            config_path,
+        })
+    }
+
+    /// Checks, without blocking, whether the config file changed since the last check.
+    #[cfg(target_os = "linux")]
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        let mut events = [0u8; 4096];
+        // Reading fails with `WouldBlock` once every pending event was read.
+        while let Ok(len) = self.inotify.read(&mut events) {
+            if len == 0 {
+                break;
+            }
+            changed |= self.mentions_config(&events[..len]);
+        }
+        changed
+    }
+
+    /// Checks, without blocking, whether the config file changed since the last check.
+    #[cfg(not(target_os = "linux"))]
+    pub fn poll(&mut self) -> bool {
+        let stamp = file_stamp(&self.config_path);
+        let changed = stamp != self.stamp;
+        self.stamp = stamp;
+        changed
+    }
+
+    /// Blocks until the config file changes or `timeout` passes.
+    ///
+    /// # Parameters
+    ///
+    /// - `timeout`: The longest time to wait.
+    ///
+    /// # Returns
+    ///
+    /// - `true` if the config file changed, `false` if the timeout passed.
+    ///
+    #[cfg(target_os = "linux")]
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.poll() {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+
+            let mut pollfd = libc::pollfd {
+                fd: self.inotify.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout_ms = remaining.as_millis().clamp(1, i32::MAX as u128) as i32;
+            if unsafe { libc::poll(&mut pollfd, 1, timeout_ms) } < 0
+                && io::Error::last_os_error().kind() != io::ErrorKind::Interrupted
+            {
+                std::thread::sleep(remaining);
+                return self.poll();
+            }
+        }
+    }
+
+    /// Blocks until the config file changes or `timeout` passes.
+    ///
+    /// # Parameters
+    ///
+    /// - `timeout`: The longest time to wait.
+    ///
+    /// # Returns
+    ///
+    /// - `true` if the config file changed, `false` if the timeout passed.
+    ///
+    #[cfg(not(target_os = "linux"))]
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.poll() {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            thread::sleep(remaining.min(POLL_INTERVAL));
+        }
+    }
+
+    /// Checks whether a buffer of inotify events contains one for the config file.
+    #[cfg(target_os = "linux")]
+    fn mentions_config(&self, events: &[u8]) -> bool {
+        const HEADER_LEN: usize = std::mem::size_of::<libc::inotify_event>();
+
+        let mut offset = 0;
+        while offset + HEADER_LEN <= events.len() {
+            // The header ends with the length of the NUL-padded name that follows it.
+            let len_bytes = &events[offset + HEADER_LEN - 4..offset + HEADER_LEN];
+            let name_len = u32::from_ne_bytes(len_bytes.try_into().unwrap_or_default()) as usize;
+            let name = events
+                .get(offset + HEADER_LEN..offset + HEADER_LEN + name_len)
+                .unwrap_or_default();
+            let name = name.split(|&b| b == 0).next().unwrap_or_default();
+
+            if name == self.file_name.as_bytes() {
+                return true;
+            }
+            offset += HEADER_LEN + name_len;
+        }
+        false
+    }
+}
+
+/// Splits the config path into the directory to watch and the file name to look for.
+#[cfg(target_os = "linux")]
+fn config_location() -> io::Result<(PathBuf, OsString)> {
+    let config_path = get_config_path();
+    match (config_path.parent(), config_path.file_name()) {
+        (Some(dir), Some(name)) => Ok((dir.to_path_buf(), name.to_os_string())),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} has no parent directory", config_path.display()),
+        )),
+    }
+}
+
+/// Returns the modification time and size of `path`, or `None` if it does not exist.
+#[cfg(not(target_os = "linux"))]
+fn file_stamp(path: &std::path::Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
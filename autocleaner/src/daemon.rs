@@ -0,0 +1,181 @@
+use crate::{
+    config_watcher::ConfigWatcher,
+    notifier::{exec_path, notify_cleanup, notify_expired_files},
+};
+use autocleaner_core::{
+    cleanup::{remove_old_files, scan_folder},
+    config::{FolderConfig, ScheduledAction, TrackedFolder},
+    journal::{record_cleanup, record_scan},
+    progress::Progress,
+    schedule::ScheduleState,
+};
+use chrono::{DateTime, Local, Utc};
+use std::{collections::HashMap, thread, time::Duration};
+
+/// The longest the daemon sleeps between checks, so config changes are picked up even if
+/// the config file cannot be watched.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Runs the daemon until the process is terminated.
+///
+/// Every iteration reloads the [`FolderConfig`], evaluates each scheduled folder that is
+/// due according to the persisted [`ScheduleState`], and then sleeps until the next run
+/// or for at most [`MAX_SLEEP`]. The sleep ends early when the config file changes, see
+/// [`ConfigWatcher`]. While the config or the schedule state cannot be loaded, no folder
+/// is evaluated and the schedule state is kept as it is. Folders whose run was missed while the daemon was not
+/// running are due immediately, so they are caught up once on start.
+///
+/// Depending on the folder's [`ScheduledAction`], a due folder is either scanned with a
+/// notification about the files found, or cleaned right away. Every run is recorded in
+/// the journal and logged to stdout.
+///
+pub fn run_daemon() {
+    let mut reported: HashMap<String, String> = HashMap::new();
+    let mut config_error = None;
+    let mut state_error = None;
+    log("▶ Daemon started");
+
+    let mut watcher = match ConfigWatcher::new() {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            log(&format!("⚠ Not watching the config for changes: {}", e));
+            None
+        }
+    };
+
+    loop {
+        let config = match FolderConfig::load_or_recover() {
+            Ok((config, recovery)) => {
+                if let Some(recovery) = recovery {
+                    log(&format!("♻ Recovered the config: {}", recovery));
+                }
+                config
+            }
+            Err(e) => {
+                let error = e.to_string();
+                if config_error.as_ref() != Some(&error) {
+                    log(&format!("⚠ The config could not be loaded: {}", error));
+                    config_error = Some(error);
+                }
+                pause(&mut watcher, MAX_SLEEP);
+                continue;
+            }
+        };
+        config_error = None;
+        let mut state = match ScheduleState::load() {
+            Ok(state) => state,
+            Err(e) => {
+                let error = e.to_string();
+                if state_error.as_ref() != Some(&error) {
+                    log(&format!(
+                        "⚠ The schedule state could not be loaded: {}",
+                        error
+                    ));
+                    state_error = Some(error);
+                }
+                pause(&mut watcher, MAX_SLEEP);
+                continue;
+            }
+        };
+        state_error = None;
+        let mut saved = state.clone();
+
+        let errors = state.sync(&config.folders, Utc::now());
+        for (path, error) in &errors {
+            if reported.get(path) != Some(error) {
+                log(&format!("⚠ {} is not scheduled: {}", path, error));
+            }
+        }
+        reported = errors.into_iter().collect();
+
+        for path in state.due(Utc::now()) {
+            let Some(folder) = config.folders.iter().find(|f| f.path == path) else {
+                continue;
+            };
+            run_folder(folder, config.scan_workers);
+
+            state.mark_run(&path, Utc::now());
+            if let Some(next) = state.folders.get(&path) {
+                log(&format!(
+                    "⏱ Next run of {} at {}",
+                    path,
+                    next.next_run.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                ));
+            }
+            save_state(&state, &mut saved);
+        }
+        save_state(&state, &mut saved);
+
+        pause(&mut watcher, sleep_until(state.next_wakeup()));
+    }
+}
+
+/// Scans a due folder, and cleans it up or notifies about the files found.
+fn run_folder(folder: &TrackedFolder, workers: usize) {
+    let plan = scan_folder(folder, workers, &Progress::default());
+
+    match folder.scheduled_action {
+        ScheduledAction::Notify => {
+            if let Err(e) = record_scan(&plan) {
+                log(&format!("⚠ Failed to write journal: {}", e));
+            }
+            log(&format!(
+                "🔍 {}: {} files to remove, {} errors",
+                folder.path,
+                plan.len(),
+                plan.errors.len()
+            ));
+            notify_expired_files(plan.len(), plan.errors.len(), exec_path());
+        }
+        ScheduledAction::Clean => {
+            let report = remove_old_files(&plan, &Progress::default());
+            if let Err(e) = record_cleanup(&plan, &report) {
+                log(&format!("⚠ Failed to write journal: {}", e));
+            }
+            log(&format!("🗑 {}: {}", folder.path, report.summary()));
+            notify_cleanup(&plan, &report);
+        }
+    }
+}
+
+/// Writes `state` to disk unless it equals the `saved` copy, which is updated on success.
+///
+/// The state is saved after every run, so a run is not repeated if the daemon stops
+/// before the iteration ends.
+///
+fn save_state(state: &ScheduleState, saved: &mut ScheduleState) {
+    if state == saved {
+        return;
+    }
+    match state.save() {
+        Ok(()) => *saved = state.clone(),
+        Err(e) => log(&format!("⚠ Failed to save the schedule state: {}", e)),
+    }
+}
+
+/// Returns how long to sleep before `next_wakeup`, at most [`MAX_SLEEP`].
+fn sleep_until(next_wakeup: Option<DateTime<Utc>>) -> Duration {
+    next_wakeup.map_or(MAX_SLEEP, |next| {
+        (next - Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO)
+            .min(MAX_SLEEP)
+    })
+}
+
+/// Sleeps for `duration`, or until the config file changes if it is watched.
+fn pause(watcher: &mut Option<ConfigWatcher>, duration: Duration) {
+    match watcher {
+        Some(watcher) => {
+            if watcher.wait(duration) {
+                log("🔄 Config changed, reloading");
+            }
+        }
+        None => thread::sleep(duration),
+    }
+}
+
+/// Prints a line to stdout, prefixed with the local time.
+fn log(message: &str) {
+    println!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+}
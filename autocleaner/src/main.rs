@@ -1,118 +1,164 @@
 use crate::{
-    config::get_app_data_dir, config::get_config_path, notifier::scan_and_notify,
+    cli::Command,
+    daemon::run_daemon,
+    notifier::{notify_config_error, notify_config_recovered, notify_purge_error, scan_and_notify},
+};
+use autocleaner_core::{
+    config::{get_app_data_dir, FolderConfig},
+    quarantine::purge_expired,
     settings::save_exec_path,
 };
 use gui::launch_gui;
 use singleton_lock::acquire_singleton_lock;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
 use std::{
-    env, fs,
+    env,
     io::{self},
-    os::windows::process::CommandExt,
+    process::ExitCode,
 };
+#[cfg(windows)]
 use tray::start_tray_icon;
+#[cfg(windows)]
 use winapi::um::winbase::CREATE_NO_WINDOW;
 
 mod app;
-mod cleanup;
-mod config;
+mod autosave;
+mod cli;
+mod config_watcher;
+mod daemon;
 mod gui;
 mod notifier;
-mod settings;
+mod preview;
 mod shared_flag;
+#[cfg(any(windows, test))]
+mod shell_link;
 mod singleton_lock;
 mod startup;
+#[cfg(target_os = "linux")]
+mod systemd;
+mod task;
+#[cfg(windows)]
 mod tray;
 
 /// Launches both the GUI and the system tray icon as background processes.
+///
+/// The tray icon is only available on Windows, so elsewhere only the GUI is launched.
+///
 fn launch_gui_and_tray() -> io::Result<()> {
-    let _ = std::process::Command::new(std::env::current_exe().unwrap())
-        .arg("tray")
-        .creation_flags(CREATE_NO_WINDOW)
-        .spawn();
-
-    let _ = std::process::Command::new(std::env::current_exe().unwrap())
-        .arg("gui")
-        .creation_flags(CREATE_NO_WINDOW)
-        .spawn();
+    #[cfg(windows)]
+    spawn_mode("tray")?;
+    spawn_mode("gui")
+}
 
-    Ok(())
+/// Starts this executable again in the given mode, without a console window on Windows.
+fn spawn_mode(mode: &str) -> io::Result<()> {
+    let mut command = std::process::Command::new(env::current_exe()?);
+    command.arg(mode);
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+    command.spawn().map(|_| ())
 }
 
 /// Entry point for the AutoCleaner application.
 ///
 /// ### Supported Modes:
 ///
-/// - `--gui`  
+/// - `gui`  
 ///   Launches the graphical user interface (GUI).  
 ///   Prevents multiple instances by locking `gui.lock`.
 ///
-/// - `--tray`  
-///   Starts the system tray icon with menu options.  
+/// - `tray`  
+///   Starts the system tray icon with menu options. Windows only.  
 ///   Prevents multiple instances by locking `tray.lock`.
 ///
-/// - `--tray-startup`  
-///   Used at Windows startup. Performs a silent scan with notification,
-///   then launches the tray icon.  
+/// - `tray-startup`  
+///   Used at startup. Purges expired quarantined files, performs a
+///   silent scan with notification, then launches the tray icon on Windows.  
 ///   Also protected by `tray.lock`.
 ///
+/// - `daemon`  
+///   Keeps running and evaluates each folder on its schedule, see [`daemon::run_daemon`].  
+///   Prevents multiple instances by locking `daemon.lock`.
+///
+/// - `scan`, `clean`, `list`, `add`, `remove`, `config show`, `history`, `restore`, `purge`,
+///   `systemd`  
+///   Headless commands run by [`cli::run`], see [`cli::Cli`].
+///
 /// - *(no argument)*  
 ///   Saves the current executable path (for startup configuration),  
 ///   then launches both GUI and tray components.
 ///
-fn main() -> io::Result<()> {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() >= 2 {
-        return match args[1].trim_start_matches('-') {
-            "gui" => {
-                let lock_path = get_app_data_dir().join("gui.lock");
-                let _singleton_guard = match acquire_singleton_lock(lock_path) {
-                    Some(guard) => guard,
-                    None => {
-                        return Ok(());
-                    }
-                };
+/// The desktop modes are also accepted with a leading `--`, e.g. `--tray-startup`.
+///
+fn main() -> ExitCode {
+    let cli = cli::parse();
 
-                launch_gui();
+    match cli.command {
+        Some(Command::Gui) => {
+            let lock_path = get_app_data_dir().join("gui.lock");
+            let Some(_singleton_guard) = acquire_singleton_lock(lock_path) else {
+                return ExitCode::SUCCESS;
+            };
 
-                Ok(())
-            }
-            "tray" => {
-                let lock_path = get_app_data_dir().join("tray.lock");
-                let _singleton_guard = match acquire_singleton_lock(lock_path) {
-                    Some(guard) => guard,
-                    None => {
-                        return Ok(());
-                    }
-                };
+            launch_gui();
+        }
+        #[cfg(windows)]
+        Some(Command::Tray) => {
+            let lock_path = get_app_data_dir().join("tray.lock");
+            let Some(_singleton_guard) = acquire_singleton_lock(lock_path) else {
+                return ExitCode::SUCCESS;
+            };
 
-                start_tray_icon();
+            start_tray_icon();
+        }
+        #[cfg(not(windows))]
+        Some(Command::Tray) => {
+            eprintln!("error: the tray icon is only available on Windows");
+            return ExitCode::FAILURE;
+        }
+        Some(Command::TrayStartup) => {
+            let lock_path = get_app_data_dir().join("tray.lock");
+            let Some(_singleton_guard) = acquire_singleton_lock(lock_path) else {
+                return ExitCode::SUCCESS;
+            };
 
-                Ok(())
-            }
-            "tray-startup" => {
-                let lock_path = get_app_data_dir().join("tray.lock");
-                let _singleton_guard = match acquire_singleton_lock(lock_path) {
-                    Some(guard) => guard,
-                    None => {
-                        return Ok(());
+            match FolderConfig::load_or_recover() {
+                Ok((config, recovery)) => {
+                    if let Some(recovery) = recovery {
+                        notify_config_recovered(&recovery);
+                    }
+                    if let Err(e) = purge_expired(config.quarantine_days) {
+                        notify_purge_error(&e);
                     }
-                };
+                    scan_and_notify();
+                }
+                Err(e) => notify_config_error(&e),
+            }
+            #[cfg(windows)]
+            start_tray_icon();
+        }
+        Some(Command::Daemon) => {
+            let lock_path = get_app_data_dir().join("daemon.lock");
+            let Some(_singleton_guard) = acquire_singleton_lock(lock_path) else {
+                eprintln!("error: the daemon is already running");
+                return ExitCode::FAILURE;
+            };
 
-                scan_and_notify();
-                start_tray_icon();
+            run_daemon();
+        }
+        Some(_) => return cli::run(&cli),
+        None => {
+            if let Ok(path) = env::current_exe() {
+                let _ = save_exec_path(path.to_string_lossy().to_string());
+            }
 
-                Ok(())
+            if let Err(e) = launch_gui_and_tray() {
+                eprintln!("Failed to launch AutoCleaner: {}", e);
+                return ExitCode::FAILURE;
             }
-            _ => Ok(()),
-        };
-    } else {
-        if let Ok(path) = std::env::current_exe() {
-            let _ = save_exec_path(path.to_string_lossy().to_string());
         }
-
-        launch_gui_and_tray()?
     }
 
-    Ok(())
+    ExitCode::SUCCESS
 }
@@ -1,85 +1,311 @@
-use crate::{
-    cleanup::scan_folder, config::FolderConfig, config::TrackedFolder, env, settings::get_exec_path,
+#[cfg(windows)]
+use autocleaner_core::undo::UndoReport;
+use autocleaner_core::{
+    cleanup::{scan_folder, CleanupPlan, CleanupReport},
+    config::{ConfigRecovery, FolderConfig, TrackedFolder},
+    journal::record_scan,
+    progress::Progress,
+    settings::get_exec_path,
 };
-use std::path::Path;
-use win_toast_notify::{Action, ActivationType, WinToastNotify};
+use std::{env, path::Path};
 
-/// Displays a Windows toast notification if expired files are found.
+/// Displays a desktop notification if expired files are found.
 ///
-/// This function triggers a toast notification using `WinToastNotify` to inform the user
-/// that expired files were found during a scan. If no expired files are found (`count == 0`),
-/// the function exits no notification is shown.
+/// This function triggers a toast notification (`WinToastNotify` on Windows, `notify-send`
+/// elsewhere) to inform the user that expired files were found during a scan. If no
+/// expired files are found and no path failed to scan, the function exits and no
+/// notification is shown.
 ///
 /// The notification includes:
 /// - A message indicating how many expired files were found.
-/// - An action button that opens the folder containing the executable.
+/// - A message indicating how many paths could not be scanned, if any.
+/// - On Windows, an action button that opens the folder containing the executable.
 ///
 /// # Parameters
 ///
-/// - `count`: The number of expired files found. If zero, no notification is shown.
+/// - `count`: The number of expired files found.
+/// - `errors`: The number of paths that could not be scanned.
 /// - `exe_path`: The path to the current executable. Used to derive the folder to open.
 ///
-pub fn notify_expired_files(count: usize, exe_path: String) {
-    if count == 0 {
+pub fn notify_expired_files(count: usize, errors: usize, exe_path: String) {
+    if count == 0 && errors == 0 {
         return;
     }
 
-    let folder_path = Path::new(&exe_path)
-        .parent()
-        .unwrap_or_else(|| Path::new("."))
-        .to_string_lossy()
-        .to_string();
-
-    let message = format!("🧹 {} expired files found", count);
-
-    WinToastNotify::new()
-        .set_app_id("Autocleaner.App")
-        .set_title("AutoCleaner")
-        .set_messages(vec![&message])
-        .set_actions(vec![Action {
-            activation_type: ActivationType::Protocol,
-            action_content: "Open AutoCleaner".to_string(),
-            arguments: folder_path,
-            image_url: None,
-        }])
-        .show()
-        .expect("Failed to show toast notification");
+    let mut messages = vec![format!("🧹 {} expired files found", count)];
+    if errors > 0 {
+        messages.push(format!("⚠ {} paths could not be scanned", errors));
+    }
+
+    platform::show_notification(&messages, Some(exec_folder(&exe_path)));
+}
+
+/// Displays a desktop notification describing the outcome of undoing a cleanup.
+///
+/// # Parameters
+///
+/// - `result`: What [`autocleaner_core::undo::undo_last_cleanup`] returned.
+///
+#[cfg(windows)]
+pub fn notify_undo(result: &std::io::Result<Option<UndoReport>>) {
+    let mut messages = Vec::new();
+    match result {
+        Ok(None) => messages.push("ℹ No cleanup to undo".to_string()),
+        Ok(Some(report)) if report.not_undoable => {
+            messages.push(format!(
+                "ℹ The last cleanup of {} cannot be undone",
+                report.folder
+            ));
+            messages.push(report.summary());
+        }
+        Ok(Some(report)) => {
+            messages.push(format!("↶ Undid cleanup of {}", report.folder));
+            messages.push(report.summary());
+        }
+        Err(e) => messages.push(format!("⚠ The undo could not be recorded: {}", e)),
+    }
+
+    platform::show_notification(&messages, None);
+}
+
+/// Displays a desktop notification that the startup entry could not be changed.
+///
+/// # Parameters
+///
+/// - `enabled`: Whether startup was being enabled or disabled.
+/// - `error`: What [`crate::startup::set_startup`] returned.
+///
+#[cfg(windows)]
+pub fn notify_startup_error(enabled: bool, error: &std::io::Error) {
+    let action = if enabled { "enable" } else { "disable" };
+    platform::show_notification(
+        &[format!("⚠ Failed to {} startup", action), error.to_string()],
+        None,
+    );
+}
+
+/// Displays a desktop notification that the config could not be loaded.
+///
+/// # Parameters
+///
+/// - `error`: What [`FolderConfig::load`] returned.
+///
+pub fn notify_config_error(error: &std::io::Error) {
+    platform::show_notification(
+        &[
+            "⚠ The config could not be loaded".to_string(),
+            error.to_string(),
+        ],
+        None,
+    );
+}
+
+/// Displays a desktop notification that the config was damaged and restored from a backup.
+///
+/// # Parameters
+///
+/// - `recovery`: What [`FolderConfig::load_or_recover`] returned.
+///
+pub fn notify_config_recovered(recovery: &ConfigRecovery) {
+    platform::show_notification(
+        &[
+            "♻ The config was restored from a backup".to_string(),
+            recovery.to_string(),
+        ],
+        None,
+    );
+}
+
+/// Displays a desktop notification that a task of the tray icon ended unexpectedly.
+///
+/// # Parameters
+///
+/// - `task`: What the task was doing, e.g. `Undoing the last cleanup`.
+///
+#[cfg(windows)]
+pub fn notify_task_panicked(task: &str) {
+    platform::show_notification(&[format!("⚠ {} failed unexpectedly", task)], None);
+}
+
+/// Displays a desktop notification that expired quarantined files could not be purged.
+///
+/// # Parameters
+///
+/// - `error`: What [`autocleaner_core::quarantine::purge_expired`] returned.
+///
+pub fn notify_purge_error(error: &std::io::Error) {
+    platform::show_notification(
+        &[
+            "⚠ Failed to purge expired quarantined files".to_string(),
+            error.to_string(),
+        ],
+        None,
+    );
 }
 
 /// Scans all tracked folders for expired files and sends a notification if any are found.
 ///
 /// This function:
-/// - Loads the current [`FolderConfig`] from disk.
-/// - Scans all tracked folders for files older than their configured threshold.
-/// - If expired files are found, it triggers a toast notification via [`notify_expired_files`].
+/// - Loads the current [`FolderConfig`] from disk. If it cannot be loaded, a notification
+///   reports the error instead.
+/// - Builds a [`CleanupPlan`] for every tracked folder via [`plan_folders`] and records
+///   each one in the journal.
+/// - If expired files are found or paths failed to scan, it triggers a toast notification
+///   via [`notify_expired_files`].
 ///
 /// The path to the current executable is used to construct a folder path that is passed
 /// to the notification action (so the app can be reopened easily from the toast).
 ///
 pub fn scan_and_notify() {
-    let path = get_exec_path()
-        .unwrap_or_else(|| env::current_exe().unwrap().to_string_lossy().to_string());
+    let path = exec_path();
+
+    let config = match FolderConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            notify_config_error(&e);
+            return;
+        }
+    };
+    let plans = plan_folders(&config.folders, config.scan_workers);
+    for plan in &plans {
+        if let Err(e) = record_scan(plan) {
+            eprintln!("Failed to write journal: {}", e);
+        }
+    }
+    let count = plans.iter().map(CleanupPlan::len).sum();
+    let errors = plans.iter().map(|p| p.errors.len()).sum();
+
+    notify_expired_files(count, errors, path);
+}
+
+/// Displays a desktop notification describing a cleanup run by the daemon.
+///
+/// Nothing is shown if the cleanup neither removed a file nor failed for one.
+///
+/// # Parameters
+///
+/// - `plan`: The plan that was executed.
+/// - `report`: The [`CleanupReport`] returned for it.
+///
+pub fn notify_cleanup(plan: &CleanupPlan, report: &CleanupReport) {
+    let failed = plan.errors.len() + report.failed.len();
+    if report.deleted.is_empty() && failed == 0 {
+        return;
+    }
 
-    let folders = FolderConfig::load().folders;
-    let count = count_expired_from_folders(&folders);
+    let mut messages = vec![format!(
+        "🗑 {} files removed from {}",
+        report.deleted.len(),
+        plan.root
+    )];
+    if failed > 0 {
+        messages.push(format!(
+            "⚠ {} paths could not be scanned or removed",
+            failed
+        ));
+    }
+
+    platform::show_notification(&messages, Some(exec_folder(&exec_path())));
+}
 
-    notify_expired_files(count, path);
+/// Returns the path to the installed executable, falling back to the running one.
+pub fn exec_path() -> String {
+    get_exec_path().unwrap_or_else(|| env::current_exe().unwrap().to_string_lossy().to_string())
 }
 
-/// Counts all expired files across a list of tracked folders.
+/// Returns the folder containing the executable at `exe_path`, opened by notification actions.
+fn exec_folder(exe_path: &str) -> String {
+    Path::new(exe_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Builds a cleanup plan for each folder in a list of tracked folders.
 ///
 /// This function iterates over each [`TrackedFolder`] in the provided slice and
-/// sums up the number of files that are older than the specified threshold (`days`)
-/// in each folder, using [`scan_folder`].
+/// builds a dry-run [`CleanupPlan`] for it using [`scan_folder`].
 ///
 /// # Parameters
 ///
 /// - `folders`: A slice of [`TrackedFolder`] items representing the folders to scan.
+/// - `workers`: The number of threads walking each folder, where `0` means one per CPU core.
 ///
 /// # Returns
 ///
-/// - The total number of expired files found across all folders.
+/// - One [`CleanupPlan`] per tracked folder, in the same order.
 ///
-pub fn count_expired_from_folders(folders: &[TrackedFolder]) -> usize {
-    folders.iter().map(|f| scan_folder(&f.path, f.days)).sum()
+pub fn plan_folders(folders: &[TrackedFolder], workers: usize) -> Vec<CleanupPlan> {
+    folders
+        .iter()
+        .map(|folder| scan_folder(folder, workers, &Progress::default()))
+        .collect()
+}
+
+#[cfg(windows)]
+mod platform {
+    use win_toast_notify::{Action, ActivationType, WinToastNotify};
+
+    /// Shows a toast with one line per message.
+    ///
+    /// A toast that cannot be shown is logged to stderr together with its messages, so a
+    /// failure never takes down the daemon or the tray.
+    ///
+    /// # Parameters
+    ///
+    /// - `messages`: The lines of the toast.
+    /// - `open_folder`: If set, the toast gets an "Open AutoCleaner" button opening this folder.
+    ///
+    pub fn show_notification(messages: &[String], open_folder: Option<String>) {
+        let actions = open_folder
+            .map(|folder| Action {
+                activation_type: ActivationType::Protocol,
+                action_content: "Open AutoCleaner".to_string(),
+                arguments: folder,
+                image_url: None,
+            })
+            .into_iter()
+            .collect();
+
+        let shown = WinToastNotify::new()
+            .set_app_id("Autocleaner.App")
+            .set_title("AutoCleaner")
+            .set_messages(messages.iter().map(String::as_str).collect())
+            .set_actions(actions)
+            .show();
+        if let Err(e) = shown {
+            eprintln!(
+                "⚠ Failed to show toast notification: {}\n{}",
+                e,
+                messages.join("\n")
+            );
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::process::Command;
+
+    /// Shows a notification through `notify-send`, or prints the messages if no
+    /// notification daemon is available, e.g. on a headless server.
+    ///
+    /// # Parameters
+    ///
+    /// - `messages`: The lines of the notification.
+    /// - `open_folder`: Ignored. Notifications have no actions on this platform.
+    ///
+    pub fn show_notification(messages: &[String], open_folder: Option<String>) {
+        let _ = open_folder;
+        let body = messages.join("\n");
+
+        let shown = Command::new("notify-send")
+            .args(["--app-name=AutoCleaner", "AutoCleaner", &body])
+            .status()
+            .is_ok_and(|status| status.success());
+        if !shown {
+            println!("{}", body);
+        }
+    }
 }
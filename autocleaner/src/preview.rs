@@ -0,0 +1,310 @@
+use autocleaner_core::cleanup::{CleanupPlan, PlannedFile};
+use chrono::Utc;
+use eframe::egui::{self, Grid, ScrollArea, TextEdit, Ui};
+use std::path::Path;
+
+/// The height of one row in the preview table, used to only lay out visible rows.
+const ROW_HEIGHT: f32 = 20.0;
+
+/// The column a [`PlanPreview`] is sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortColumn {
+    Path,
+    Size,
+    Age,
+    Rule,
+}
+
+impl std::fmt::Display for SortColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortColumn::Path => write!(f, "Path"),
+            SortColumn::Size => write!(f, "Size"),
+            SortColumn::Age => write!(f, "Age"),
+            SortColumn::Rule => write!(f, "Rule"),
+        }
+    }
+}
+
+/// What the user asked for in a [`PlanPreview`] table.
+///
+/// # Variants
+///
+/// - `Exclude`: Spare the file at this index of the plan, and exclude it from future
+///   scans of the folder.
+///
+pub enum PreviewAction {
+    Exclude(usize),
+}
+
+/// A [`CleanupPlan`] shown as a table in which individual files can be spared.
+///
+/// Every file starts out checked. Only checked files are part of [`PlanPreview::checked_plan`],
+/// which is what "Remove" executes.
+///
+/// # Fields
+///
+/// - `plan`: The plan built by the last scan.
+/// - `checked`: Whether each file of the plan will be removed, by index.
+/// - `filter`: Only files whose path or rule contains this text are shown.
+/// - `sort`: The column the table is sorted by.
+/// - `descending`: Whether the table is sorted in descending order.
+/// - `order`: The indices of the shown files in display order, rebuilt when the filter or
+///   sorting changes.
+///
+pub struct PlanPreview {
+    pub plan: CleanupPlan,
+    pub checked: Vec<bool>,
+    pub filter: String,
+    pub sort: SortColumn,
+    pub descending: bool,
+    order: Vec<usize>,
+}
+
+impl PlanPreview {
+    /// Wraps a freshly scanned plan with every file checked, sorted by path.
+    pub fn new(plan: CleanupPlan) -> Self {
+        let mut preview = Self {
+            checked: vec![true; plan.len()],
+            plan,
+            filter: String::new(),
+            sort: SortColumn::Path,
+            descending: false,
+            order: Vec::new(),
+        };
+        preview.refresh_order();
+        preview
+    }
+
+    /// Returns the number of checked files.
+    pub fn checked_count(&self) -> usize {
+        self.checked.iter().filter(|&&checked| checked).count()
+    }
+
+    /// Returns the combined size in bytes of the checked files.
+    pub fn checked_size(&self) -> u64 {
+        self.checked_files().map(|f| f.size).sum()
+    }
+
+    /// Returns a copy of the plan containing only the checked files.
+    pub fn checked_plan(&self) -> CleanupPlan {
+        CleanupPlan {
+            files: self.checked_files().cloned().collect(),
+            ..self.plan.clone()
+        }
+    }
+
+    fn checked_files(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.plan
+            .files
+            .iter()
+            .zip(&self.checked)
+            .filter(|(_, &checked)| checked)
+            .map(|(file, _)| file)
+    }
+
+    /// Removes the file at `index` from the plan.
+    ///
+    /// # Returns
+    ///
+    /// An exclude pattern matching exactly that file, relative to the tracked folder.
+    ///
+    pub fn exclude(&mut self, index: usize) -> String {
+        let file = self.plan.files.remove(index);
+        self.checked.remove(index);
+        self.refresh_order();
+
+        let relative = file
+            .path
+            .strip_prefix(&self.plan.root)
+            .unwrap_or(&file.path);
+        exclude_pattern(relative)
+    }
+
+    /// Rebuilds the display order from the filter and sorting.
+    fn refresh_order(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let files = &self.plan.files;
+
+        self.order = (0..files.len())
+            .filter(|&i| {
+                filter.is_empty()
+                    || files[i]
+                        .path
+                        .to_string_lossy()
+                        .to_lowercase()
+                        .contains(&filter)
+                    || files[i].rule.to_string().to_lowercase().contains(&filter)
+            })
+            .collect();
+
+        match self.sort {
+            SortColumn::Path => self
+                .order
+                .sort_by(|&a, &b| files[a].path.cmp(&files[b].path)),
+            SortColumn::Size => self.order.sort_by_key(|&i| files[i].size),
+            // Older files have an earlier timestamp, so ascending age is descending time.
+            SortColumn::Age => self
+                .order
+                .sort_by_key(|&i| std::cmp::Reverse(files[i].timestamp)),
+            SortColumn::Rule => self
+                .order
+                .sort_by_cached_key(|&i| files[i].rule.to_string()),
+        }
+        if self.descending {
+            self.order.reverse();
+        }
+    }
+
+    /// Draws the filter, selection buttons and the table of planned files.
+    ///
+    /// Only the visible rows are laid out, so plans with many files stay responsive.
+    ///
+    /// # Parameters
+    ///
+    /// - `ui`: The UI to draw into.
+    /// - `id`: A value unique to the folder, such as its path, keeping the tables apart.
+    ///
+    /// # Returns
+    ///
+    /// The [`PreviewAction`] the user triggered, if any.
+    ///
+    pub fn show(&mut self, ui: &mut Ui, id: &str) -> Option<PreviewAction> {
+        let mut action = None;
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label("🔎 Filter:");
+            changed |= ui
+                .add(TextEdit::singleline(&mut self.filter).hint_text("path or rule"))
+                .changed();
+
+            if ui.button("☑ Check shown").clicked() {
+                for &i in &self.order {
+                    self.checked[i] = true;
+                }
+            }
+            if ui.button("☐ Uncheck shown").clicked() {
+                for &i in &self.order {
+                    self.checked[i] = false;
+                }
+            }
+
+            ui.label(format!("{} to reclaim", format_size(self.checked_size())));
+        });
+
+        ScrollArea::both()
+            .id_source(("preview", id))
+            .max_height(250.0)
+            .show_rows(ui, ROW_HEIGHT, self.order.len() + 1, |ui, rows| {
+                Grid::new(("preview_grid", id))
+                    .striped(true)
+                    .min_row_height(ROW_HEIGHT)
+                    .show(ui, |ui| {
+                        if rows.start == 0 {
+                            ui.label("");
+                            for column in [
+                                SortColumn::Path,
+                                SortColumn::Size,
+                                SortColumn::Age,
+                                SortColumn::Rule,
+                            ] {
+                                changed |= self.sort_header(ui, column);
+                            }
+                            ui.label("");
+                            ui.end_row();
+                        }
+
+                        let now = Utc::now();
+                        for row in rows.start.max(1)..rows.end {
+                            let index = self.order[row - 1];
+                            let file = &self.plan.files[index];
+
+                            ui.checkbox(&mut self.checked[index], "");
+                            let relative = file
+                                .path
+                                .strip_prefix(&self.plan.root)
+                                .unwrap_or(&file.path);
+                            ui.label(relative.display().to_string());
+                            ui.label(format_size(file.size));
+                            ui.label(format!("{} days", (now - file.timestamp).num_days()));
+                            ui.label(file.rule.to_string());
+                            if ui
+                                .small_button("🚫 Always keep")
+                                .on_hover_text("Exclude this file from future cleanups")
+                                .clicked()
+                            {
+                                action = Some(PreviewAction::Exclude(index));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if changed {
+            self.refresh_order();
+        }
+        action
+    }
+
+    /// Draws a clickable column header that sorts by `column`, toggling the direction
+    /// when it is already sorted by it.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the sorting changed.
+    ///
+    fn sort_header(&mut self, ui: &mut Ui, column: SortColumn) -> bool {
+        let selected = self.sort == column;
+        let arrow = match (selected, self.descending) {
+            (false, _) => "",
+            (true, false) => " ⏶",
+            (true, true) => " ⏷",
+        };
+
+        if !ui
+            .selectable_label(
+                selected,
+                egui::RichText::new(format!("{}{}", column, arrow)).strong(),
+            )
+            .clicked()
+        {
+            return false;
+        }
+
+        if selected {
+            self.descending = !self.descending;
+        } else {
+            self.sort = column;
+            self.descending = false;
+        }
+        true
+    }
+}
+
+/// Builds an exclude pattern matching exactly the file at `relative`.
+fn exclude_pattern(relative: &Path) -> String {
+    let components: Vec<_> = relative
+        .components()
+        .map(|c| globset::escape(&c.as_os_str().to_string_lossy()))
+        .collect();
+    components.join("/")
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The size of the `ShellLinkHeader`, which is also its first field.
+const HEADER_SIZE: u32 = 0x4C;
+
+/// The class identifier every shell link starts with, `00021401-0000-0000-C000-000000000046`.
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+/// The `LinkFlags` this module reads and writes.
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+
+/// `FILE_ATTRIBUTE_NORMAL`, the attributes recorded for the target.
+const FILE_ATTRIBUTE_NORMAL: u32 = 0x80;
+
+/// `SW_SHOWNORMAL`, the window state the target is opened with.
+const SW_SHOWNORMAL: u32 = 1;
+
+/// The size of a `LinkInfo` header with the Unicode path offsets.
+const LINK_INFO_HEADER_SIZE: u32 = 0x24;
+
+/// The `LinkInfoFlags` bit indicating a `VolumeID` and `LocalBasePath`.
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+
+/// `DRIVE_FIXED`, the drive type recorded in the `VolumeID`.
+const DRIVE_FIXED: u32 = 3;
+
+/// A Windows shortcut (`.lnk` file) as described by the Shell Link Binary File Format
+/// ([MS-SHLLINK]).
+///
+/// Only the parts AutoCleaner needs are supported: the target is stored as a local path in
+/// the `LinkInfo` structure, followed by the Unicode string data. Reading skips a target ID
+/// list, so shortcuts created by Explorer can be read as well.
+///
+/// [MS-SHLLINK]: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-shllink
+///
+/// # Fields
+///
+/// - `target`: The absolute path of the file the shortcut opens.
+/// - `arguments`: The command line arguments passed to the target.
+/// - `working_dir`: The directory the target is started in, if any.
+/// - `icon_location`: The file the shortcut's icon is taken from, if any.
+/// - `icon_index`: The index of the icon within `icon_location`.
+/// - `description`: The comment shown as the shortcut's tooltip, if any.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShellLink {
+    pub target: PathBuf,
+    pub arguments: String,
+    pub working_dir: Option<PathBuf>,
+    pub icon_location: Option<PathBuf>,
+    pub icon_index: i32,
+    pub description: Option<String>,
+}
+
+impl ShellLink {
+    /// Creates a shortcut to `target` without arguments, working directory or icon.
+    pub fn new(target: impl Into<PathBuf>) -> Self {
+        Self {
+            target: target.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Reads a shortcut from a `.lnk` file.
+    ///
+    /// # Returns
+    ///
+    /// - The parsed shortcut, or an `Err` if the file cannot be read or is not a shell link
+    ///   with a local target path.
+    ///
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Writes the shortcut to a `.lnk` file, replacing an existing one.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Encodes the shortcut in the Shell Link Binary File Format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let strings = [
+            (HAS_NAME, self.description.clone()),
+            (
+                HAS_WORKING_DIR,
+                self.working_dir
+                    .as_ref()
+                    .map(|dir| dir.to_string_lossy().to_string()),
+            ),
+            (
+                HAS_ARGUMENTS,
+                Some(self.arguments.clone()).filter(|arguments| !arguments.is_empty()),
+            ),
+            (
+                HAS_ICON_LOCATION,
+                self.icon_location
+                    .as_ref()
+                    .map(|icon| icon.to_string_lossy().to_string()),
+            ),
+        ];
+        let flags = strings
+            .iter()
+            .filter(|(_, value)| value.is_some())
+            .fold(HAS_LINK_INFO | IS_UNICODE, |flags, (flag, _)| flags | flag);
+
+        let mut bytes = Vec::new();
+        put_u32(&mut bytes, HEADER_SIZE);
+        bytes.extend_from_slice(&LINK_CLSID);
+        put_u32(&mut bytes, flags);
+        put_u32(&mut bytes, FILE_ATTRIBUTE_NORMAL);
+        bytes.extend_from_slice(&[0; 24]); // creation, access and write time
+        put_u32(&mut bytes, 0); // file size
+        bytes.extend_from_slice(&self.icon_index.to_le_bytes());
+        put_u32(&mut bytes, SW_SHOWNORMAL);
+        bytes.extend_from_slice(&[0; 12]); // hot key and reserved fields
+
+        bytes.extend_from_slice(&link_info(&self.target.to_string_lossy()));
+
+        for value in strings.into_iter().filter_map(|(_, value)| value) {
+            let units: Vec<u16> = value.encode_utf16().collect();
+            bytes.extend_from_slice(&(units.len() as u16).to_le_bytes());
+            units
+                .iter()
+                .for_each(|unit| bytes.extend_from_slice(&unit.to_le_bytes()));
+        }
+
+        put_u32(&mut bytes, 0); // terminal extra data block
+        bytes
+    }
+
+    /// Decodes a shortcut from the Shell Link Binary File Format.
+    ///
+    /// # Returns
+    ///
+    /// - The parsed shortcut, or an `Err` with [`io::ErrorKind::InvalidData`] if the data
+    ///   is truncated, is not a shell link, or has no local target path.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.u32()? != HEADER_SIZE || reader.take(16)? != LINK_CLSID {
+            return Err(invalid_data("not a shell link"));
+        }
+        let flags = reader.u32()?;
+        reader.take(4 + 24 + 4)?; // attributes, times and file size
+        let icon_index = reader.u32()? as i32;
+        reader.offset = HEADER_SIZE as usize;
+
+        if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            let size = reader.u16()?;
+            reader.take(size.into())?;
+        }
+        if flags & HAS_LINK_INFO == 0 {
+            return Err(invalid_data("the shell link has no local target path"));
+        }
+        let info_size = reader.u32()? as usize;
+        reader.offset -= 4; // the size includes its own field
+        let target = read_link_info(reader.take(info_size)?)
+            .ok_or_else(|| invalid_data("the shell link has no local target path"))?;
+
+        let mut link = ShellLink {
+            target: PathBuf::from(target),
+            icon_index,
+            ..ShellLink::default()
+        };
+        for flag in [
+            HAS_NAME,
+            HAS_RELATIVE_PATH,
+            HAS_WORKING_DIR,
+            HAS_ARGUMENTS,
+            HAS_ICON_LOCATION,
+        ] {
+            if flags & flag == 0 {
+                continue;
+            }
+            let value = reader.string_data(flags & IS_UNICODE != 0)?;
+            match flag {
+                HAS_NAME => link.description = Some(value),
+                HAS_WORKING_DIR => link.working_dir = Some(PathBuf::from(value)),
+                HAS_ARGUMENTS => link.arguments = value,
+                HAS_ICON_LOCATION => link.icon_location = Some(PathBuf::from(value)),
+                _ => {}
+            }
+        }
+        Ok(link)
+    }
+}
+
+/// Encodes a `LinkInfo` structure pointing at the local path `target`.
+///
+/// The path is stored in Unicode, and in ASCII with other characters replaced by `?` for
+/// readers that only support the ANSI field.
+///
+fn link_info(target: &str) -> Vec<u8> {
+    let volume_id = {
+        let mut volume_id = Vec::new();
+        put_u32(&mut volume_id, 0x11); // size
+        put_u32(&mut volume_id, DRIVE_FIXED);
+        put_u32(&mut volume_id, 0); // serial number
+        put_u32(&mut volume_id, 0x10); // offset of the empty label
+        volume_id.push(0);
+        volume_id
+    };
+    let ansi_path: Vec<u8> = target
+        .chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .chain([0])
+        .collect();
+    let unicode_path: Vec<u8> = target
+        .encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect();
+
+    let volume_id_offset = LINK_INFO_HEADER_SIZE;
+    let local_base_path_offset = volume_id_offset + volume_id.len() as u32;
+    let common_path_suffix_offset = local_base_path_offset + ansi_path.len() as u32;
+    let local_base_path_unicode_offset = common_path_suffix_offset + 1;
+    let common_path_suffix_unicode_offset =
+        local_base_path_unicode_offset + unicode_path.len() as u32;
+    let size = common_path_suffix_unicode_offset + 2;
+
+    let mut info = Vec::new();
+    put_u32(&mut info, size);
+    put_u32(&mut info, LINK_INFO_HEADER_SIZE);
+    put_u32(&mut info, VOLUME_ID_AND_LOCAL_BASE_PATH);
+    put_u32(&mut info, volume_id_offset);
+    put_u32(&mut info, local_base_path_offset);
+    put_u32(&mut info, 0); // no network location
+    put_u32(&mut info, common_path_suffix_offset);
+    put_u32(&mut info, local_base_path_unicode_offset);
+    put_u32(&mut info, common_path_suffix_unicode_offset);
+    info.extend_from_slice(&volume_id);
+    info.extend_from_slice(&ansi_path);
+    info.push(0); // empty common path suffix
+    info.extend_from_slice(&unicode_path);
+    info.extend_from_slice(&[0, 0]); // empty Unicode common path suffix
+    info
+}
+
+/// Reads the local target path from a `LinkInfo` structure, preferring the Unicode fields.
+///
+/// # Returns
+///
+/// - The local base path joined with the common path suffix, or `None` if the structure
+///   is malformed or has no local base path.
+///
+fn read_link_info(info: &[u8]) -> Option<String> {
+    let field = |index: usize| -> Option<usize> {
+        let bytes = info.get(index * 4..index * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    };
+    let header_size = field(1)?;
+    if field(2)? as u32 & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+
+    if header_size >= LINK_INFO_HEADER_SIZE as usize {
+        let base = utf16_at(info, field(7)?)?;
+        let suffix = utf16_at(info, field(8)?)?;
+        return Some(base + &suffix);
+    }
+    let base = ansi_at(info, field(4)?)?;
+    let suffix = ansi_at(info, field(6)?)?;
+    Some(base + &suffix)
+}
+
+/// Reads a NUL-terminated single-byte string at `offset`, decoding bytes as Latin-1.
+fn ansi_at(bytes: &[u8], offset: usize) -> Option<String> {
+    let rest = bytes.get(offset..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    Some(rest[..end].iter().map(|&b| b as char).collect())
+}
+
+/// Reads a NUL-terminated UTF-16LE string at `offset`.
+fn utf16_at(bytes: &[u8], offset: usize) -> Option<String> {
+    let units: Vec<u16> = bytes
+        .get(offset..)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Appends a little-endian `u32`.
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Creates the error returned for malformed shell links.
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// A cursor over the bytes of a shell link.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Returns the next `len` bytes, or an `Err` if the data ends before.
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| invalid_data("the shell link is truncated"))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Reads a `StringData` entry: a character count followed by UTF-16LE or
+    /// single-byte characters.
+    fn string_data(&mut self, unicode: bool) -> io::Result<String> {
+        let count = self.u16()? as usize;
+        if unicode {
+            let units: Vec<u16> = self
+                .take(count * 2)?
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16(&units).map_err(|_| invalid_data("invalid UTF-16 in shell link"))
+        } else {
+            Ok(self.take(count)?.iter().map(|&b| b as char).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_link_round_trips() {
+        let link = ShellLink {
+            target: PathBuf::from(r"C:\Users\O'Brien\Apps\AutoCléaner\autocleaner.exe"),
+            arguments: "--tray-startup".to_string(),
+            working_dir: Some(PathBuf::from(r"C:\Users\O'Brien\Apps\AutoCléaner")),
+            icon_location: Some(PathBuf::from(
+                r"C:\Users\O'Brien\Apps\AutoCléaner\autocleaner.exe",
+            )),
+            icon_index: 0,
+            description: Some("AutoCleaner".to_string()),
+        };
+
+        let bytes = link.to_bytes();
+        assert_eq!(&bytes[..4], &HEADER_SIZE.to_le_bytes());
+        assert_eq!(&bytes[4..20], &LINK_CLSID);
+        assert_eq!(ShellLink::from_bytes(&bytes).unwrap(), link);
+
+        let minimal = ShellLink::new(r"D:\autocleaner.exe");
+        assert_eq!(ShellLink::from_bytes(&minimal.to_bytes()).unwrap(), minimal);
+
+        let path = std::env::temp_dir().join(format!("autocleaner-{}.lnk", std::process::id()));
+        link.save(&path).unwrap();
+        assert_eq!(ShellLink::load(&path).unwrap(), link);
+        fs::remove_file(&path).unwrap();
+
+        assert!(ShellLink::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+        assert!(ShellLink::from_bytes(b"not a shortcut").is_err());
+    }
+}
@@ -1,5 +1,77 @@
-use crate::CREATE_NO_WINDOW;
-use std::{os::windows::process::CommandExt, path::PathBuf};
+#[cfg(target_os = "windows")]
+use crate::shell_link::ShellLink;
+#[cfg(target_os = "linux")]
+use crate::systemd::{
+    are_units_installed, install_service_unit, is_timer_enabled, service_exe_path,
+    set_timer_enabled,
+};
+use autocleaner_core::settings::get_exec_path;
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use std::fs;
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+};
+
+/// Returns the executable a new or repaired startup entry runs.
+///
+/// The path saved with [`save_exec_path`](autocleaner_core::settings::save_exec_path)
+/// when AutoCleaner is launched without arguments is preferred, so running another build
+/// in a single mode does not move the entry. If no path was saved or the saved binary is
+/// gone, e.g. because a portable install was moved, the running executable is used.
+///
+/// # Returns
+///
+/// - The path of an existing executable, or an `Err` if neither path exists.
+///
+pub fn resolve_startup_target() -> io::Result<PathBuf> {
+    if let Some(stored) = get_exec_path().map(PathBuf::from) {
+        if stored.is_file() {
+            return Ok(stored);
+        }
+    }
+
+    let current = env::current_exe()?;
+    if current.is_file() {
+        Ok(current)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", current.display()),
+        ))
+    }
+}
+
+/// Points an enabled startup entry at [`resolve_startup_target()`] if it runs a missing
+/// or different binary.
+///
+/// # Returns
+///
+/// - `Some` with the new target if the entry was rewritten.
+/// - `None` if startup is disabled or the entry is up to date.
+/// - An `Err` if no target could be resolved or the entry could not be written.
+///
+pub fn repair_startup() -> io::Result<Option<PathBuf>> {
+    if !is_startup_enabled() {
+        return Ok(None);
+    }
+
+    let target = resolve_startup_target()?;
+    if read_startup_target().is_some_and(|current| is_same_file(&current, &target)) {
+        return Ok(None);
+    }
+    write_startup_entry(&target)?;
+    Ok(Some(target))
+}
+
+/// Checks whether both paths lead to the same file, comparing them as given if either
+/// cannot be resolved.
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
 
 /// Returns the path to the AutoCleaner startup shortcut on Windows.
 ///
@@ -14,57 +86,301 @@ pub fn get_startup_shortcut_path() -> PathBuf {
         .join("Microsoft\\Windows\\Start Menu\\Programs\\Startup\\AutoCleaner.lnk")
 }
 
+/// Writes the startup shortcut, running `target` with the `--tray-startup` argument from
+/// its own directory and showing its icon.
+#[cfg(target_os = "windows")]
+fn write_startup_entry(target: &Path) -> io::Result<()> {
+    let link = ShellLink {
+        arguments: "--tray-startup".to_string(),
+        working_dir: target.parent().map(Path::to_path_buf),
+        icon_location: Some(target.to_path_buf()),
+        description: Some("AutoCleaner".to_string()),
+        ..ShellLink::new(target)
+    };
+
+    let shortcut_path = get_startup_shortcut_path();
+    if let Some(parent) = shortcut_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    link.save(&shortcut_path)
+}
+
+/// Reads the target of the startup shortcut.
+///
+/// # Returns
+///
+/// - The shortcut's target path, or `None` if it does not exist or cannot be parsed.
+///
+#[cfg(target_os = "windows")]
+fn read_startup_target() -> Option<PathBuf> {
+    ShellLink::load(&get_startup_shortcut_path())
+        .ok()
+        .map(|link| link.target)
+}
+
 /// Enables or disables AutoCleaner running at Windows startup.
 ///
 /// This function creates or removes a `.lnk` shortcut in the Windows Startup folder
 /// to control whether the app launches automatically when the user logs into Windows.
 ///
-/// - When `enabled` is `true`, a shortcut to `AutoCleaner.exe` is created with `--tray-startup` argument.
+/// - When `enabled` is `true`, a shortcut to [`resolve_startup_target()`] is created with
+///   the `--tray-startup` argument.
 /// - When `enabled` is `false`, the shortcut is removed if it exists.
 ///
 /// # Parameters
 ///
 /// - `enabled`: If `true`, adds the app to startup. If `false`, removes it.
 ///
+/// # Returns
+///
+/// - An `Err` if no executable could be resolved or the shortcut could not be written or
+///   removed.
+///
 #[cfg(target_os = "windows")]
-pub fn set_startup(enabled: bool) {
-    use std::process::Command;
+pub fn set_startup(enabled: bool) -> io::Result<()> {
+    if enabled {
+        resolve_startup_target().and_then(|target| write_startup_entry(&target))
+    } else {
+        remove_if_exists(&get_startup_shortcut_path())
+    }
+}
 
-    let shortcut_path = get_startup_shortcut_path();
+/// Returns the path to the AutoCleaner XDG autostart entry on Linux.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] pointing to `~/.config/autostart/autocleaner.desktop`, honoring
+///   `XDG_CONFIG_HOME`.
+///
+#[cfg(target_os = "linux")]
+pub fn get_autostart_entry_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("autostart")
+        .join("autocleaner.desktop")
+}
+
+/// Generates the XDG autostart entry, which runs `tray-startup` when the user logs into a
+/// desktop session.
+///
+/// # Parameters
+///
+/// - `exe_path`: The AutoCleaner executable to run.
+///
+/// # Returns
+///
+/// - The contents of `autocleaner.desktop`.
+///
+#[cfg(target_os = "linux")]
+fn autostart_entry(exe_path: &str) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=AutoCleaner\n\
+         Comment=Removes old files from tracked folders\n\
+         Exec={} tray-startup\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        quote_desktop_exec_arg(exe_path)
+    )
+}
+
+/// Quotes an `Exec=` argument as described by the Desktop Entry Specification.
+///
+/// Inside the quotes, `"`, `` ` ``, `$` and `\` are escaped with a backslash. The value is
+/// then escaped once more as a desktop entry string, and `%` is doubled so it is not taken
+/// for a field code.
+///
+#[cfg(target_os = "linux")]
+fn quote_desktop_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted.replace('\\', "\\\\").replace('%', "%%")
+}
+
+/// Returns the program of an `Exec=` value, undoing [`quote_desktop_exec_arg`].
+///
+/// # Parameters
+///
+/// - `exec`: The value of the `Exec=` key, e.g. `"/opt/my app/autocleaner" tray-startup`.
+///
+/// # Returns
+///
+/// - The first argument of the command line, or `None` if it is empty.
+///
+#[cfg(target_os = "linux")]
+fn desktop_exec_program(exec: &str) -> Option<String> {
+    let exec = exec.replace("\\\\", "\\").replace("%%", "%");
+    let exec = exec.trim_start();
+    let mut program = String::new();
+
+    if let Some(quoted) = exec.strip_prefix('"') {
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => program.extend(chars.next()),
+                c => program.push(c),
+            }
+        }
+    } else {
+        program.extend(exec.chars().take_while(|c| !c.is_whitespace()));
+    }
+    (!program.is_empty()).then_some(program)
+}
+
+/// Reads the `[Desktop Entry]` keys of the XDG autostart entry.
+///
+/// # Returns
+///
+/// - The key-value pairs of the entry, or `None` if it does not exist.
+///
+#[cfg(target_os = "linux")]
+fn read_autostart_entry() -> Option<Vec<(String, String)>> {
+    let content = fs::read_to_string(get_autostart_entry_path()).ok()?;
+    let mut in_entry = false;
+    let mut keys = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if let Some((key, value)) = line.split_once('=').filter(|_| in_entry) {
+            keys.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Some(keys)
+}
 
+/// Returns the value of the last occurrence of `name` among desktop entry `keys`.
+#[cfg(target_os = "linux")]
+fn entry_value<'a>(keys: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    keys.iter()
+        .rev()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Points the startup mechanism at `target` and enables it: the systemd timer if its
+/// units are installed, the XDG autostart entry otherwise.
+#[cfg(target_os = "linux")]
+fn write_startup_entry(target: &Path) -> io::Result<()> {
+    if are_units_installed() {
+        install_service_unit(target)?;
+        return set_timer_enabled(true);
+    }
+
+    let path = get_autostart_entry_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, autostart_entry(&target.to_string_lossy()))
+}
+
+/// Reads the executable run by the enabled startup mechanism.
+///
+/// # Returns
+///
+/// - The program of the systemd service if the timer is enabled, or else of the XDG
+///   autostart entry. `None` if neither can be read.
+///
+#[cfg(target_os = "linux")]
+fn read_startup_target() -> Option<PathBuf> {
+    if is_timer_enabled() {
+        return service_exe_path();
+    }
+
+    let keys = read_autostart_entry()?;
+    entry_value(&keys, "Exec")
+        .and_then(desktop_exec_program)
+        .map(PathBuf::from)
+}
+
+/// Enables or disables AutoCleaner running at login on Linux.
+///
+/// - When `enabled` is `true` and the systemd units of `autocleaner systemd install` are
+///   present, their timer is enabled. Otherwise `autocleaner.desktop` is written to the
+///   autostart folder and runs [`resolve_startup_target()`] with the `tray-startup`
+///   argument.
+/// - When `enabled` is `false`, both are turned off: the timer is disabled if it is
+///   enabled, and the autostart entry is removed if it exists. Either one would keep
+///   [`is_startup_enabled()`] reporting `true`.
+///
+/// # Parameters
+///
+/// - `enabled`: If `true`, adds the app to startup. If `false`, removes it.
+///
+/// # Returns
+///
+/// - An `Err` if no executable could be resolved, the entry could not be written or
+///   removed, or `systemctl` failed.
+///
+#[cfg(target_os = "linux")]
+pub fn set_startup(enabled: bool) -> io::Result<()> {
     if enabled {
-        let exe_path = PathBuf::from("C:\\Program Files\\AutoCleaner\\autocleaner.exe");
-        let target = exe_path.to_string_lossy();
-        let _ = Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-WindowStyle",
-                "Hidden",
-                "-Command",
-                &format!(
-                    r#"$s=(New-Object -COM WScript.Shell).CreateShortcut('{}');$s.TargetPath='{}';$s.Arguments='--tray-startup';$s.Save()"#,
-                    shortcut_path.display(),
-                    target
-                ),
-            ])
-            .creation_flags(CREATE_NO_WINDOW)
-            .spawn();
+        resolve_startup_target().and_then(|target| write_startup_entry(&target))
     } else {
-        let _ = std::fs::remove_file(shortcut_path);
+        if is_timer_enabled() {
+            set_timer_enabled(false)?;
+        }
+        remove_if_exists(&get_autostart_entry_path())
+    }
+}
+
+/// Removes a file, succeeding if it does not exist.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
     }
 }
 
-/// Dummy implementation of [`set_startup`] for non-Windows platforms.
+/// Dummy implementation of [`set_startup`] for platforms other than Windows and Linux.
 ///
-/// This function exists to provide cross-platform compatibility but does nothing
-/// on platforms other than Windows.
+/// This function exists to provide cross-platform compatibility but cannot add the app
+/// to startup.
 ///
 /// # Parameters
 ///
-/// - `_enabled`: Ignored. Present only to match the Windows function signature.
+/// - `enabled`: Whether the app should be added to startup.
 ///
-#[cfg(not(target_os = "windows"))]
-pub fn set_startup(_enabled: bool) {}
+/// # Returns
+///
+/// - An `Err` if `enabled` is `true`, as startup entries are not supported.
+///
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn set_startup(enabled: bool) -> io::Result<()> {
+    if enabled {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "startup entries are not supported on this platform",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Dummy implementation of `write_startup_entry` for platforms other than Windows and
+/// Linux, which have no startup entry to write.
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn write_startup_entry(_target: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "startup entries are not supported on this platform",
+    ))
+}
+
+/// Dummy implementation of `read_startup_target` for platforms other than Windows and
+/// Linux.
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn read_startup_target() -> Option<PathBuf> {
+    None
+}
 
 /// Checks whether AutoCleaner is set to run at Windows startup.
 ///
@@ -76,6 +392,43 @@ pub fn set_startup(_enabled: bool) {}
 /// - `true` if the startup shortcut exists.
 /// - `false` otherwise.
 ///
+#[cfg(target_os = "windows")]
 pub fn is_startup_enabled() -> bool {
     get_startup_shortcut_path().exists()
 }
+
+/// Checks whether AutoCleaner is set to run at login on Linux.
+///
+/// The XDG autostart entry counts as enabled if it runs `tray-startup` and is neither
+/// hidden nor switched off with `X-GNOME-Autostart-enabled=false`, as desktop settings
+/// apps do.
+///
+/// # Returns
+///
+/// - `true` if `autocleaner.timer` or the XDG autostart entry is enabled.
+/// - `false` otherwise.
+///
+#[cfg(target_os = "linux")]
+pub fn is_startup_enabled() -> bool {
+    if is_timer_enabled() {
+        return true;
+    }
+
+    read_autostart_entry().is_some_and(|keys| {
+        entry_value(&keys, "Exec").is_some_and(|exec| exec.ends_with(" tray-startup"))
+            && entry_value(&keys, "Hidden") != Some("true")
+            && entry_value(&keys, "X-GNOME-Autostart-enabled") != Some("false")
+    })
+}
+
+/// Dummy implementation of [`is_startup_enabled`] for platforms other than Windows and
+/// Linux.
+///
+/// # Returns
+///
+/// - Always `false`, as [`set_startup`] does nothing on these platforms.
+///
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn is_startup_enabled() -> bool {
+    false
+}
@@ -0,0 +1,249 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The name of the service unit running `autocleaner clean`.
+pub const SERVICE_NAME: &str = "autocleaner.service";
+
+/// The name of the timer unit starting [`SERVICE_NAME`].
+pub const TIMER_NAME: &str = "autocleaner.timer";
+
+/// Returns the directory systemd loads the current user's units from.
+///
+/// # Returns
+///
+/// - A [`PathBuf`] pointing to `~/.config/systemd/user`, honoring `XDG_CONFIG_HOME`.
+///
+pub fn get_user_unit_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("systemd")
+        .join("user")
+}
+
+/// Generates the service unit, which cleans every tracked folder once.
+///
+/// Exit code `3` of a partial cleanup does not mark the service as failed, so a single
+/// locked file does not show up in `systemctl --user --failed`.
+///
+/// # Parameters
+///
+/// - `exe_path`: The AutoCleaner executable to run.
+///
+/// # Returns
+///
+/// - The contents of [`SERVICE_NAME`].
+///
+pub fn service_unit(exe_path: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description=AutoCleaner cleanup of tracked folders\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart={} clean\n\
+         SuccessExitStatus=3\n",
+        quote_exec_arg(&exe_path.to_string_lossy())
+    )
+}
+
+/// Generates the timer unit, which starts the service shortly after login and then on
+/// the given calendar schedule. Runs missed while the computer was off are caught up.
+///
+/// # Parameters
+///
+/// - `on_calendar`: The `OnCalendar=` expression, e.g. `daily` or `*-*-* 03:00`.
+///
+/// # Returns
+///
+/// - The contents of [`TIMER_NAME`].
+///
+pub fn timer_unit(on_calendar: &str) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Run AutoCleaner cleanup ({})\n\
+         \n\
+         [Timer]\n\
+         OnStartupSec=2min\n\
+         OnCalendar={}\n\
+         Persistent=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        on_calendar, on_calendar
+    )
+}
+
+/// Quotes an `ExecStart=` argument, escaping the characters systemd would expand.
+fn quote_exec_arg(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+/// Returns the program of an `ExecStart=` value, undoing [`quote_exec_arg`].
+///
+/// # Parameters
+///
+/// - `exec_start`: The value of `ExecStart=`, e.g. `"/opt/my app/autocleaner" clean`.
+///
+/// # Returns
+///
+/// - The first argument of the command line, or `None` if it is empty.
+///
+fn exec_program(exec_start: &str) -> Option<String> {
+    let exec_start = exec_start.trim_start();
+    let mut program = String::new();
+
+    if let Some(quoted) = exec_start.strip_prefix('"') {
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => program.extend(chars.next()),
+                c => program.push(c),
+            }
+        }
+    } else {
+        program.extend(exec_start.chars().take_while(|c| !c.is_whitespace()));
+    }
+    let program = program.replace("%%", "%").replace("$$", "$");
+    (!program.is_empty()).then_some(program)
+}
+
+/// Writes the service and timer units and reloads systemd.
+///
+/// The timer is not enabled, see [`set_timer_enabled`].
+///
+/// # Parameters
+///
+/// - `exe_path`: The AutoCleaner executable the service runs.
+/// - `on_calendar`: The `OnCalendar=` expression of the timer.
+///
+/// # Returns
+///
+/// - The paths of the written service and timer units, or an `Err` if a unit could not be
+///   written or `systemctl` failed.
+///
+pub fn install_units(exe_path: &Path, on_calendar: &str) -> io::Result<(PathBuf, PathBuf)> {
+    let timer_path = get_user_unit_dir().join(TIMER_NAME);
+    fs::create_dir_all(get_user_unit_dir())?;
+    fs::write(&timer_path, timer_unit(on_calendar))?;
+
+    let service_path = install_service_unit(exe_path)?;
+    Ok((service_path, timer_path))
+}
+
+/// Writes only the service unit and reloads systemd, keeping the timer's schedule.
+///
+/// # Parameters
+///
+/// - `exe_path`: The AutoCleaner executable the service runs.
+///
+/// # Returns
+///
+/// - The path of the service unit, or an `Err` if it could not be written or
+///   `systemctl` failed.
+///
+pub fn install_service_unit(exe_path: &Path) -> io::Result<PathBuf> {
+    let service_path = get_user_unit_dir().join(SERVICE_NAME);
+    fs::create_dir_all(get_user_unit_dir())?;
+    fs::write(&service_path, service_unit(exe_path))?;
+
+    systemctl(&["daemon-reload"])?;
+    Ok(service_path)
+}
+
+/// Reads the executable the installed service unit runs.
+///
+/// # Returns
+///
+/// - The program of its `ExecStart=`, or `None` if the unit does not exist or has none.
+///
+pub fn service_exe_path() -> Option<PathBuf> {
+    let content = fs::read_to_string(get_user_unit_dir().join(SERVICE_NAME)).ok()?;
+    content
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("ExecStart="))
+        .and_then(exec_program)
+        .map(PathBuf::from)
+}
+
+/// Disables the timer, removes both units and reloads systemd.
+///
+/// # Returns
+///
+/// - The paths of the removed units, or an `Err` if a unit could not be removed or
+///   `systemctl` failed. Units that are not installed are skipped.
+///
+pub fn uninstall_units() -> io::Result<Vec<PathBuf>> {
+    if !are_units_installed() {
+        return Ok(Vec::new());
+    }
+    set_timer_enabled(false)?;
+
+    let mut removed = Vec::new();
+    for name in [TIMER_NAME, SERVICE_NAME] {
+        let path = get_user_unit_dir().join(name);
+        if path.exists() {
+            fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+
+    systemctl(&["daemon-reload"])?;
+    Ok(removed)
+}
+
+/// Enables and starts, or disables and stops the timer.
+///
+/// # Parameters
+///
+/// - `enabled`: Whether the timer should run.
+///
+/// # Returns
+///
+/// - An `Err` if `systemctl` could not be run or failed.
+///
+pub fn set_timer_enabled(enabled: bool) -> io::Result<()> {
+    let action = if enabled { "enable" } else { "disable" };
+    systemctl(&[action, "--now", TIMER_NAME])
+}
+
+/// Checks whether both units exist in [`get_user_unit_dir()`].
+pub fn are_units_installed() -> bool {
+    let unit_dir = get_user_unit_dir();
+    unit_dir.join(SERVICE_NAME).exists() && unit_dir.join(TIMER_NAME).exists()
+}
+
+/// Checks whether the timer is enabled, i.e. linked into `timers.target.wants`.
+pub fn is_timer_enabled() -> bool {
+    get_user_unit_dir()
+        .join("timers.target.wants")
+        .join(TIMER_NAME)
+        .symlink_metadata()
+        .is_ok()
+}
+
+/// Runs `systemctl --user` with the given arguments.
+fn systemctl(args: &[&str]) -> io::Result<()> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "systemctl --user {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
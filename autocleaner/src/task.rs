@@ -0,0 +1,237 @@
+use autocleaner_core::{
+    cleanup::{remove_old_files, scan_folder, CleanupPlan, CleanupReport},
+    config::TrackedFolder,
+    journal::{record_cleanup, record_scan},
+    progress::Progress,
+    quarantine::{purge_expired, restore},
+    undo::{undo_last_cleanup, UndoReport},
+};
+use std::{
+    io,
+    path::PathBuf,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+/// What a [`BackgroundTask`] is doing.
+///
+/// # Variants
+///
+/// - `Scan`: Building a [`CleanupPlan`].
+/// - `Cleanup`: Executing a plan of `total` files.
+/// - `Purge`: Permanently deleting expired quarantined files.
+/// - `Restore`: Moving a quarantined file back to its original path.
+/// - `Undo`: Restoring the files of the most recent cleanup that can be undone.
+///
+#[derive(Clone, Copy, Debug)]
+pub enum TaskKind {
+    Scan,
+    Cleanup { total: usize },
+    Purge,
+    Restore,
+    Undo,
+}
+
+/// The result of a finished [`BackgroundTask`].
+///
+/// `journal` holds the error if the run could not be recorded in the journal. `purged`,
+/// `restored` and `report` hold what [`purge_expired`], [`restore`] and
+/// [`undo_last_cleanup`] returned.
+///
+pub enum TaskOutput {
+    Scan {
+        plan: CleanupPlan,
+        journal: io::Result<()>,
+    },
+    Cleanup {
+        plan: CleanupPlan,
+        report: CleanupReport,
+        journal: io::Result<()>,
+    },
+    Purge {
+        purged: io::Result<usize>,
+    },
+    Restore {
+        restored: io::Result<PathBuf>,
+    },
+    Undo {
+        report: io::Result<Option<UndoReport>>,
+    },
+}
+
+/// A scan or cleanup of one tracked folder, or a change to the quarantine or an undo,
+/// running on a worker thread.
+///
+/// The GUI keeps polling the task with [`BackgroundTask::try_finish`] and shows its
+/// [`Progress`] in the meantime, so the window stays responsive during long walks.
+///
+/// # Fields
+///
+/// - `folder`: The folder settings the task was started with, or `None` for a purge,
+///   restore or undo.
+/// - `kind`: Whether the task scans or cleans up.
+/// - `progress`: The counters updated by the worker thread.
+/// - `handle`: The worker thread, taken once it has been joined.
+///
+pub struct BackgroundTask {
+    pub folder: Option<TrackedFolder>,
+    pub kind: TaskKind,
+    pub progress: Arc<Progress>,
+    handle: Option<JoinHandle<TaskOutput>>,
+}
+
+impl BackgroundTask {
+    /// Starts scanning `folder` on a worker thread, recording the plan in the journal.
+    ///
+    /// # Parameters
+    ///
+    /// - `folder`: The folder to scan.
+    /// - `workers`: The number of threads walking the folder, where `0` means one per CPU core.
+    /// - `ctx`: The egui context, repainted when the task finishes.
+    ///
+    pub fn scan(folder: TrackedFolder, workers: usize, ctx: &eframe::egui::Context) -> Self {
+        let scanned = folder.clone();
+        Self::spawn(
+            Some(folder),
+            TaskKind::Scan,
+            repaint(ctx),
+            move |progress| {
+                let plan = scan_folder(&scanned, workers, progress);
+                let journal = record_scan(&plan);
+                TaskOutput::Scan { plan, journal }
+            },
+        )
+    }
+
+    /// Starts executing `plan` for `folder` on a worker thread, recording the outcome in
+    /// the journal.
+    pub fn cleanup(folder: TrackedFolder, plan: CleanupPlan, ctx: &eframe::egui::Context) -> Self {
+        let kind = TaskKind::Cleanup { total: plan.len() };
+        Self::spawn(Some(folder), kind, repaint(ctx), move |progress| {
+            let report = remove_old_files(&plan, progress);
+            let journal = record_cleanup(&plan, &report);
+            TaskOutput::Cleanup {
+                plan,
+                report,
+                journal,
+            }
+        })
+    }
+
+    /// Starts permanently deleting the files quarantined more than `grace_days` ago on a
+    /// worker thread.
+    pub fn purge(grace_days: u32, ctx: &eframe::egui::Context) -> Self {
+        Self::spawn(None, TaskKind::Purge, repaint(ctx), move |_| {
+            TaskOutput::Purge {
+                purged: purge_expired(grace_days),
+            }
+        })
+    }
+
+    /// Starts restoring the quarantined file stored at `stored_path` on a worker thread.
+    pub fn restore(stored_path: PathBuf, ctx: &eframe::egui::Context) -> Self {
+        Self::spawn(None, TaskKind::Restore, repaint(ctx), move |_| {
+            TaskOutput::Restore {
+                restored: restore(&stored_path),
+            }
+        })
+    }
+
+    /// Starts undoing the most recent cleanup that can be undone on a worker thread.
+    ///
+    /// # Parameters
+    ///
+    /// - `on_finish`: Called on the worker thread once the undo is done, so the output can
+    ///   be collected with [`BackgroundTask::try_finish`].
+    ///
+    pub fn undo(on_finish: impl FnOnce() + Send + 'static) -> Self {
+        Self::spawn(None, TaskKind::Undo, on_finish, |_| TaskOutput::Undo {
+            report: undo_last_cleanup(),
+        })
+    }
+
+    /// Runs `work` on a new thread with a fresh [`Progress`], then calls `on_finish`.
+    fn spawn(
+        folder: Option<TrackedFolder>,
+        kind: TaskKind,
+        on_finish: impl FnOnce() + Send + 'static,
+        work: impl FnOnce(&Progress) -> TaskOutput + Send + 'static,
+    ) -> Self {
+        let progress = Arc::new(Progress::default());
+        let thread_progress = Arc::clone(&progress);
+
+        let handle = thread::spawn(move || {
+            let output = work(&thread_progress);
+            on_finish();
+            output
+        });
+
+        Self {
+            folder,
+            kind,
+            progress,
+            handle: Some(handle),
+        }
+    }
+
+    /// Asks the worker thread to stop as soon as possible.
+    pub fn cancel(&self) {
+        self.progress.cancel();
+    }
+
+    /// Returns the task's output if the worker thread has finished.
+    ///
+    /// # Returns
+    ///
+    /// - `None` while the task is still running, or after the output was taken.
+    /// - `Some(Err(_))` if the worker thread panicked.
+    ///
+    pub fn try_finish(&mut self) -> Option<thread::Result<TaskOutput>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        self.handle.take().map(JoinHandle::join)
+    }
+
+    /// Waits for the worker thread and returns the task's output.
+    ///
+    /// # Returns
+    ///
+    /// - `None` if the output was already taken with [`BackgroundTask::try_finish`].
+    /// - `Some(Err(_))` if the worker thread panicked.
+    ///
+    #[cfg(windows)]
+    pub fn join(mut self) -> Option<thread::Result<TaskOutput>> {
+        self.handle.take().map(JoinHandle::join)
+    }
+
+    /// Describes the task's progress in one line for the GUI.
+    pub fn status(&self) -> String {
+        let snapshot = self.progress.snapshot();
+        let status = match self.kind {
+            TaskKind::Scan => format!(
+                "🔍 {} files visited, {} matches, {} bytes reclaimable",
+                snapshot.visited, snapshot.matched, snapshot.bytes
+            ),
+            TaskKind::Cleanup { total } => format!(
+                "🗑 {} of {} files processed, {} removed, {} bytes reclaimed",
+                snapshot.visited, total, snapshot.matched, snapshot.bytes
+            ),
+            TaskKind::Purge => "🧹 Purging expired quarantined files…".to_string(),
+            TaskKind::Restore => "↩ Restoring a quarantined file…".to_string(),
+            TaskKind::Undo => "↶ Undoing the last cleanup…".to_string(),
+        };
+
+        if self.progress.is_cancelled() {
+            format!("{} — cancelling…", status)
+        } else {
+            status
+        }
+    }
+}
+
+/// Returns a callback that repaints the GUI, so it collects a finished task right away.
+fn repaint(ctx: &eframe::egui::Context) -> impl FnOnce() + Send + 'static {
+    let ctx = ctx.clone();
+    move || ctx.request_repaint()
+}
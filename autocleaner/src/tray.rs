@@ -1,5 +1,7 @@
 use crate::{
+    notifier::{notify_startup_error, notify_task_panicked, notify_undo},
     startup::{is_startup_enabled, set_startup},
+    task::{BackgroundTask, TaskOutput},
     CREATE_NO_WINDOW,
 };
 use std::os::windows::process::CommandExt;
@@ -18,9 +20,12 @@ pub fn start_tray_icon() {
         Exit,
         OpenGui,
         ToggleStartup,
+        UndoLastCleanup,
+        UndoFinished,
     }
     use std::sync::{Arc, Mutex};
     let (s, r) = crossbeam_channel::unbounded();
+    let undo_sender = s.clone();
     let icon = include_bytes!("../../resources/icon.ico");
 
     let icon = Icon::from_buffer(icon, None, None).unwrap();
@@ -32,6 +37,7 @@ pub fn start_tray_icon() {
             .checkable("Run at Startup", run_at_startup, Events::ToggleStartup)
             .separator()
             .item("Open GUI", Events::OpenGui)
+            .item("Undo last cleanup", Events::UndoLastCleanup)
             .separator()
             .item("Exit", Events::Exit)
     }
@@ -50,11 +56,16 @@ pub fn start_tray_icon() {
         .unwrap();
 
     std::thread::spawn(move || {
+        // The undo runs on its own thread so the menu stays responsive. A click while it
+        // runs is ignored.
+        let mut undo_task: Option<BackgroundTask> = None;
         r.iter().for_each(|m| match m {
             Events::ToggleStartup => {
                 let mut state = state_clone.lock().unwrap();
-                *state = !*state;
-                set_startup(*state);
+                if let Err(e) = set_startup(!*state) {
+                    notify_startup_error(!*state, &e);
+                }
+                *state = is_startup_enabled();
                 let new_menu = build_menu(*state);
                 tray_icon.set_menu(&new_menu).unwrap();
             }
@@ -64,6 +75,19 @@ pub fn start_tray_icon() {
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn();
             }
+            Events::UndoLastCleanup => {
+                if undo_task.is_none() {
+                    let sender = undo_sender.clone();
+                    undo_task = Some(BackgroundTask::undo(move || {
+                        let _ = sender.send(Events::UndoFinished);
+                    }));
+                }
+            }
+            Events::UndoFinished => match undo_task.take().and_then(BackgroundTask::join) {
+                Some(Ok(TaskOutput::Undo { report })) => notify_undo(&report),
+                Some(_) => notify_task_panicked("Undoing the last cleanup"),
+                None => {}
+            },
             Events::RightClickTrayIcon => {
                 let run_at_startup_state = Arc::new(Mutex::new(is_startup_enabled()));
                 let state_clone = run_at_startup_state.clone();